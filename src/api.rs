@@ -0,0 +1,312 @@
+use std::collections::{HashMap, HashSet};
+
+use rustdoc_types::{Id, Item, ItemEnum, ItemKind, StructKind, VariantKind, Visibility};
+
+use crate::targets::signature;
+use crate::{is_doc_hidden, item_kind, AnyItem, Crate};
+
+/// The set of items that downstream crates can actually name.
+///
+/// An item is reachable if it can be reached from the crate root through public modules and `pub use` re-exports
+/// (including glob re-exports), and neither it nor any module on the way is `#[doc(hidden)]`.
+/// Public fields, variants, trait items and the impls of reachable types (with their items) are reachable too.
+///
+/// Obtain it with [`Crate::public_api`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PublicApi {
+    ids: HashSet<Id>,
+}
+
+impl PublicApi {
+    pub(crate) fn new(krate: &Crate) -> Self {
+        let mut analysis = Analysis {
+            krate,
+            reachable: HashSet::new(),
+            visited_modules: HashSet::new(),
+        };
+        if let Some(root) = krate.index.get(&krate.root) {
+            analysis.reach(root);
+        }
+        Self {
+            ids: analysis.reachable,
+        }
+    }
+
+    pub fn contains(&self, id: &Id) -> bool {
+        self.ids.contains(id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &Id> {
+        self.ids.iter()
+    }
+
+    /// Reachable items, wrapped into [`AnyItem`].
+    pub fn items<'a>(&'a self, krate: &'a Crate) -> impl Iterator<Item = AnyItem<'a>> {
+        self.ids
+            .iter()
+            .filter_map(|id| krate.index.get(id))
+            .map(|item| krate.any_item(item))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+struct Analysis<'a> {
+    krate: &'a Crate,
+    reachable: HashSet<Id>,
+    /// Modules whose items have already been visited, either directly or through a glob import.
+    visited_modules: HashSet<Id>,
+}
+
+impl<'a> Analysis<'a> {
+    fn get(&self, id: &Id) -> Option<&'a Item> {
+        self.krate.index.get(id)
+    }
+
+    /// Marks `item` (and everything nested in it) as reachable.
+    fn reach(&mut self, item: &'a Item) {
+        if is_doc_hidden(item) || !self.reachable.insert(item.id) {
+            return;
+        }
+        match &item.inner {
+            ItemEnum::Module(module) if self.visited_modules.insert(item.id) => {
+                self.reach_module_items(&module.items);
+            }
+            ItemEnum::Struct(struct_) => {
+                match &struct_.kind {
+                    StructKind::Unit => {}
+                    StructKind::Tuple(fields) => self.reach_public(fields.iter().flatten()),
+                    StructKind::Plain { fields, .. } => self.reach_public(fields.iter()),
+                }
+                self.reach_impls(&struct_.impls);
+            }
+            ItemEnum::Union(union) => {
+                self.reach_public(union.fields.iter());
+                self.reach_impls(&union.impls);
+            }
+            ItemEnum::Enum(enum_) => {
+                self.reach_all(&enum_.variants);
+                self.reach_impls(&enum_.impls);
+            }
+            ItemEnum::Variant(variant) => match &variant.kind {
+                VariantKind::Plain => {}
+                VariantKind::Tuple(fields) => self.reach_all(fields.iter().flatten()),
+                VariantKind::Struct { fields, .. } => self.reach_all(fields),
+            },
+            ItemEnum::Trait(trait_) => self.reach_all(&trait_.items),
+            ItemEnum::Primitive(primitive) => self.reach_impls(&primitive.impls),
+            ItemEnum::Impl(impl_) => {
+                if impl_.trait_.is_some() {
+                    self.reach_all(&impl_.items);
+                } else {
+                    self.reach_public(impl_.items.iter());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn reach_all(&mut self, ids: impl IntoIterator<Item = &'a Id>) {
+        for id in ids {
+            if let Some(item) = self.get(id) {
+                self.reach(item);
+            }
+        }
+    }
+
+    fn reach_public(&mut self, ids: impl Iterator<Item = &'a Id>) {
+        for id in ids {
+            if let Some(item) = self.get(id) {
+                if item.visibility == Visibility::Public {
+                    self.reach(item);
+                }
+            }
+        }
+    }
+
+    fn reach_impls(&mut self, impls: &'a [Id]) {
+        for id in impls {
+            let Some(item) = self.get(id) else {
+                continue;
+            };
+            // Impls of local types for external traits are included, but not impls defined in other crates.
+            if item.crate_id == 0 {
+                self.reach(item);
+            }
+        }
+    }
+
+    fn reach_module_items(&mut self, items: &'a [Id]) {
+        for id in items {
+            let Some(item) = self.get(id) else {
+                continue;
+            };
+            if item.visibility != Visibility::Public || is_doc_hidden(item) {
+                continue;
+            }
+            let ItemEnum::Use(import) = &item.inner else {
+                self.reach(item);
+                continue;
+            };
+            let Some(target) = import.id.as_ref().and_then(|id| self.get(id)) else {
+                continue;
+            };
+            if !import.is_glob {
+                self.reach(target);
+                continue;
+            }
+            match &target.inner {
+                // `pub use foo::*;` brings the public items of `foo` into scope, but not `foo` itself.
+                ItemEnum::Module(module)
+                    if !is_doc_hidden(target) && self.visited_modules.insert(target.id) =>
+                {
+                    self.reach_module_items(&module.items);
+                }
+                ItemEnum::Enum(enum_) => self.reach_all(&enum_.variants),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The paths of the public API: the items yielded by [`Crate::walk`] (except hidden and unreachable ones),
+/// and the public fields, variants, trait items and inherent methods of types and traits as `path::member`.
+///
+/// Items re-exported under several paths appear once per path.
+pub(crate) fn api_paths(krate: &Crate) -> Vec<(String, &Item)> {
+    let api = krate.public_api();
+    let mut paths = vec![];
+    for (path, item) in krate.walk().skip_hidden(true) {
+        let item = item.item();
+        if !api.contains(&item.id) {
+            continue;
+        }
+        let members = match &item.inner {
+            ItemEnum::Struct(struct_) => {
+                let fields = match &struct_.kind {
+                    StructKind::Unit => vec![],
+                    StructKind::Tuple(fields) => fields.iter().flatten().collect(),
+                    StructKind::Plain { fields, .. } => fields.iter().collect(),
+                };
+                [fields, inherent_items(krate, &struct_.impls)].concat()
+            }
+            ItemEnum::Union(union) => [
+                union.fields.iter().collect(),
+                inherent_items(krate, &union.impls),
+            ]
+            .concat(),
+            ItemEnum::Enum(enum_) => [
+                enum_.variants.iter().collect(),
+                inherent_items(krate, &enum_.impls),
+            ]
+            .concat(),
+            ItemEnum::Trait(trait_) => trait_.items.iter().collect(),
+            _ => vec![],
+        };
+        for member in members.into_iter().filter_map(|id| krate.index.get(id)) {
+            if let Some(name) = member.name.as_deref().filter(|_| api.contains(&member.id)) {
+                paths.push((format!("{path}::{name}"), member));
+            }
+        }
+        paths.push((path, item));
+    }
+    paths
+}
+
+fn inherent_items<'a>(krate: &'a Crate, impls: &'a [Id]) -> Vec<&'a Id> {
+    impls
+        .iter()
+        .filter_map(|id| krate.index.get(id))
+        .filter_map(|item| match &item.inner {
+            ItemEnum::Impl(impl_) if impl_.trait_.is_none() => Some(&impl_.items),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// An API path together with the id it refers to in each of several builds of the same crate.
+#[derive(Debug, Clone)]
+pub(crate) struct MergedPath {
+    pub path: String,
+    pub kind: ItemKind,
+    /// Indexed like the crates passed to [`merge_api_paths`]; `None` if the path is absent from that build.
+    pub ids: Vec<Option<Id>>,
+}
+
+/// Merges the [`api_paths`] of several builds of the same crate (e.g. with different features), sorted by path.
+pub(crate) fn merge_api_paths(crates: &[&Crate]) -> Vec<MergedPath> {
+    let mut merged = HashMap::<(String, ItemKind), Vec<Option<Id>>>::new();
+    for (i, krate) in crates.iter().enumerate() {
+        for (path, item) in api_paths(krate) {
+            merged
+                .entry((path, item_kind(item)))
+                .or_insert_with(|| vec![None; crates.len()])[i] = Some(item.id);
+        }
+    }
+    let mut merged = merged
+        .into_iter()
+        .map(|((path, kind), ids)| MergedPath { path, kind, ids })
+        .collect::<Vec<_>>();
+    merged.sort_by_cached_key(|merged| (merged.path.clone(), format!("{:?}", merged.kind)));
+    merged
+}
+
+/// The differences between the public APIs of two builds of a crate, by public path (see [`ApiDiff::new`]).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ApiDiff {
+    added: Vec<(String, ItemKind)>,
+    removed: Vec<(String, ItemKind)>,
+    changed: Vec<(String, ItemKind)>,
+}
+
+impl ApiDiff {
+    /// Compares the public paths of `old` and `new`, and the signatures of the items present in both
+    /// (as compared by [`TargetItem::has_differing_signatures`](crate::targets::TargetItem::has_differing_signatures)).
+    pub fn new(old: &Crate, new: &Crate) -> Self {
+        let mut diff = Self::default();
+        for merged in merge_api_paths(&[old, new]) {
+            let entry = (merged.path, merged.kind);
+            match &merged.ids[..] {
+                [None, Some(_)] => diff.added.push(entry),
+                [Some(_), None] => diff.removed.push(entry),
+                [Some(old_id), Some(new_id)]
+                    if signature(&old.index[old_id]) != signature(&new.index[new_id]) =>
+                {
+                    diff.changed.push(entry)
+                }
+                _ => {}
+            }
+        }
+        diff
+    }
+
+    /// The paths that are only in the new build.
+    pub fn added(&self) -> impl Iterator<Item = (&str, ItemKind)> {
+        self.added.iter().map(|(path, kind)| (path.as_str(), *kind))
+    }
+
+    /// The paths that are only in the old build.
+    pub fn removed(&self) -> impl Iterator<Item = (&str, ItemKind)> {
+        self.removed
+            .iter()
+            .map(|(path, kind)| (path.as_str(), *kind))
+    }
+
+    /// The paths whose signature changed.
+    pub fn changed(&self) -> impl Iterator<Item = (&str, ItemKind)> {
+        self.changed
+            .iter()
+            .map(|(path, kind)| (path.as_str(), *kind))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
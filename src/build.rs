@@ -0,0 +1,386 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use cargo_metadata::diagnostic::{Diagnostic, DiagnosticLevel};
+use cargo_metadata::{DependencyKind, Message, Metadata, MetadataCommand, PackageId};
+
+use crate::progress::{kill, BuildEvent};
+use crate::workspace::is_library;
+use crate::{BuildCrateError, Color, CrateBuilder};
+
+impl CrateBuilder {
+    /// The `cargo` command with the toolchain, cargo path and environment variables applied.
+    pub(crate) fn cargo(&self) -> Command {
+        let mut command = match (&self.cargo_path, &self.toolchain) {
+            (Some(cargo_path), toolchain) => {
+                let mut command = Command::new(cargo_path);
+                if let Some(toolchain) = toolchain {
+                    command.env("RUSTUP_TOOLCHAIN", toolchain);
+                }
+                command
+            }
+            (None, Some(toolchain)) => {
+                let mut command = Command::new("rustup");
+                command.args(["run", toolchain, "cargo"]);
+                command
+            }
+            (None, None) => Command::new("cargo"),
+        };
+        command.envs(self.envs.iter().map(|(key, value)| (key, value)));
+        command
+    }
+
+    /// `--offline`, `--locked` and `--frozen` as requested, and the source replacement of a vendor directory.
+    fn cargo_flags(&self) -> Vec<String> {
+        let mut flags = [
+            (self.offline, "--offline"),
+            (self.locked, "--locked"),
+            (self.frozen, "--frozen"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, flag)| enabled.then_some(flag.to_string()))
+        .collect::<Vec<_>>();
+        if let Some(vendor_dir) = &self.vendor_dir {
+            let vendor_dir = std::path::absolute(vendor_dir).unwrap_or(vendor_dir.clone());
+            // A TOML string, e.g. with backslashes of Windows paths escaped
+            let directory = toml_edit::Value::from(vendor_dir.to_string_lossy().into_owned());
+            flags.extend([
+                "--config".to_string(),
+                "source.crates-io.replace-with=\"vendored-sources\"".to_string(),
+                "--config".to_string(),
+                format!("source.vendored-sources.directory={directory}"),
+            ]);
+        }
+        flags
+    }
+
+    /// A `cargo metadata` command for the manifest, using the same cargo, environment and `--offline`/`--locked`/`--frozen` flags as the build.
+    pub(crate) fn metadata_command(&self) -> MetadataCommand {
+        let mut command = MetadataCommand::new();
+        command.manifest_path(&self.manifest_path);
+        if let Some(cargo_path) = &self.cargo_path {
+            command.cargo_path(cargo_path);
+        }
+        if let Some(toolchain) = self
+            .toolchain
+            .as_ref()
+            .filter(|_| self.cargo_path.is_none())
+        {
+            command.env("RUSTUP_TOOLCHAIN", toolchain);
+        }
+        for (key, value) in &self.envs {
+            command.env(key, value);
+        }
+        command.other_options(self.cargo_flags());
+        command
+    }
+
+    /// The `cargo rustdoc` command, e.g.
+    /// `rustup run nightly cargo rustdoc --lib --manifest-path Cargo.toml --message-format json -- -Z unstable-options --output-format json`
+    fn command(&self) -> Command {
+        let mut command = self.cargo();
+        command.args(["rustdoc", "--lib"]);
+        command.args(self.cargo_flags());
+        command.arg("--manifest-path").arg(&self.manifest_path);
+        if let Some(package) = &self.package {
+            command.args(["--package", package]);
+        }
+        if let Some(target) = &self.target {
+            command.args(["--target", target]);
+        }
+        if let Some(target_dir) = &self.target_dir {
+            command.arg("--target-dir").arg(target_dir);
+        }
+        if self.no_default_features {
+            command.arg("--no-default-features");
+        }
+        if self.all_features {
+            command.arg("--all-features");
+        }
+        for feature in &self.features {
+            command.args(["--features", feature]);
+        }
+        if self.quiet {
+            command.arg("--quiet");
+        }
+        let (color, message_format) = match self.color {
+            Color::Always => ("always", "json-diagnostic-rendered-ansi"),
+            Color::Never => ("never", "json"),
+            Color::Auto => ("auto", "json"),
+        };
+        command.args(["--color", color, "--message-format", message_format]);
+        command.args(["--", "-Z", "unstable-options", "--output-format", "json"]);
+        if self.document_private_items {
+            command.arg("--document-private-items");
+        }
+        if self.document_hidden_items {
+            command.arg("--document-hidden-items");
+        }
+        for cfg in &self.cfgs {
+            command.args(["--cfg", cfg]);
+        }
+        if let Some(cap_lints) = &self.cap_lints {
+            command.args(["--cap-lints", cap_lints]);
+        }
+        if !self.rustdoc_flags.is_empty() {
+            command.env(
+                "CARGO_ENCODED_RUSTDOCFLAGS",
+                self.rustdoc_flags.join("\x1f"),
+            );
+        }
+        command
+    }
+
+    /// Runs `cargo rustdoc` and returns the path of the generated JSON.
+    ///
+    /// Compiler diagnostics are collected from cargo's JSON messages, and their rendered form is written to `stderr`
+    /// (the process's stderr if `output` is `None`) like cargo would without `--message-format json`.
+    /// `metadata` is the output of [`CrateBuilder::metadata_command`], if it was already run.
+    pub(crate) fn run(
+        &self,
+        output: Option<(&mut dyn Write, &mut dyn Write)>,
+        metadata: Option<&Metadata>,
+    ) -> Result<PathBuf, BuildCrateError> {
+        if !self.manifest_path.is_file() {
+            return Err(BuildCrateError::ManifestNotFound(
+                self.manifest_path.clone(),
+            ));
+        }
+        let started = Instant::now();
+        // Run once, for both the progress total and the path of the JSON. Its error is only returned if the build
+        // succeeds, as the build's own errors (e.g. a missing toolchain) are more specific
+        let metadata = match metadata {
+            Some(metadata) => Ok(Cow::Borrowed(metadata)),
+            None => self.metadata_command().exec().map(Cow::Owned),
+        };
+        let (root, total) = match (&self.progress, &metadata) {
+            (Some(_), Ok(metadata)) => self.dependency_count(metadata).unzip(),
+            _ => (None, None),
+        };
+        self.check_cancelled()?;
+        let mut command = self.command();
+        // Its own process group, so that cancelling kills cargo's children as well (see `progress::kill`)
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => BuildCrateError::ToolchainNotFound {
+                    toolchain: self.toolchain.clone(),
+                    message: format!("failed to run `{:?}`: {err}", command.get_program()),
+                },
+                _ => err.into(),
+            })?;
+        self.emit(BuildEvent::Started { total });
+
+        let forward = !self.silent && output.is_none();
+        let mut stderr_pipe = io::BufReader::new(child.stderr.take().unwrap());
+        let stdout_pipe = io::BufReader::new(child.stdout.take().unwrap());
+        let progress = self.progress.clone();
+        let stderr_thread = std::thread::spawn(move || -> io::Result<Vec<u8>> {
+            let mut stderr = vec![];
+            let mut line = vec![];
+            loop {
+                line.clear();
+                if stderr_pipe.read_until(b'\n', &mut line)? == 0 {
+                    return Ok(stderr);
+                }
+                if forward {
+                    let _ = io::stderr().write_all(&line);
+                }
+                if let Some(progress) = &progress {
+                    if let Some(name) = documenting(&String::from_utf8_lossy(&line)) {
+                        progress.emit(BuildEvent::Documenting {
+                            name: name.to_string(),
+                        });
+                    }
+                }
+                stderr.extend_from_slice(&line);
+            }
+        });
+        let child = Arc::new(Mutex::new(child));
+        let watchdog = self.watch(&child, started);
+
+        let (mut stdout_sink, mut stderr_sink): (Box<dyn Write + '_>, Box<dyn Write + '_>) =
+            match output {
+                Some((stdout, stderr)) => (Box::new(stdout), Box::new(stderr)),
+                None if self.silent => (Box::new(io::sink()), Box::new(io::sink())),
+                None => (Box::new(io::stdout()), Box::new(io::stderr())),
+            };
+        let mut diagnostics = vec![];
+        let mut compiled = HashSet::new();
+        let mut result = Ok(());
+        for message in Message::parse_stream(stdout_pipe) {
+            let handled = message.map_err(BuildCrateError::from).and_then(|message| {
+                match message {
+                    Message::CompilerMessage(message) => {
+                        if let Some(rendered) = &message.message.rendered {
+                            stderr_sink.write_all(rendered.as_bytes())?;
+                        }
+                        diagnostics.push(message.message);
+                    }
+                    Message::CompilerArtifact(artifact)
+                        if Some(&artifact.package_id) != root.as_ref()
+                            && !artifact.target.is_custom_build()
+                            && compiled.insert(artifact.package_id.clone()) =>
+                    {
+                        self.emit(BuildEvent::Compiled {
+                            name: artifact.target.name,
+                            done: compiled.len(),
+                            total,
+                        });
+                    }
+                    Message::TextLine(line) => writeln!(stdout_sink, "{line}")?,
+                    _ => {}
+                }
+                Ok(())
+            });
+            if let Err(err) = handled {
+                kill(&mut child.lock().unwrap_or_else(|err| err.into_inner()));
+                result = Err(err);
+                break;
+            }
+        }
+        let interrupt = watchdog.and_then(|watchdog| watchdog.finish());
+        // Always wait for cargo and the stderr reader before returning, even on errors,
+        // so that neither outlives the build
+        let status = child.lock().unwrap_or_else(|err| err.into_inner()).wait();
+        let stderr = stderr_thread
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("failed to read the stderr of cargo")));
+        if let Some(interrupt) = interrupt {
+            return Err(interrupt);
+        }
+        result?;
+        let status = status?;
+        let stderr = stderr?;
+        if !forward {
+            stderr_sink.write_all(&stderr)?;
+        }
+
+        if status.success() {
+            self.json_path(&*metadata?)
+        } else {
+            Err(self.classify_failure(
+                status,
+                diagnostics,
+                String::from_utf8_lossy(&stderr).into_owned(),
+            ))
+        }
+    }
+
+    /// The root package and the number of packages it depends on (excluding dev-dependencies),
+    /// i.e. the number of dependencies cargo compiles before documenting it.
+    fn dependency_count(&self, metadata: &Metadata) -> Option<(PackageId, usize)> {
+        let root = match &self.package {
+            Some(name) => metadata
+                .packages
+                .iter()
+                .find(|package| &package.name == name)?
+                .id
+                .clone(),
+            None => metadata.root_package()?.id.clone(),
+        };
+        let resolve = metadata.resolve.as_ref()?;
+        let nodes = resolve
+            .nodes
+            .iter()
+            .map(|node| (&node.id, node))
+            .collect::<HashMap<_, _>>();
+        let mut seen = HashSet::new();
+        let mut stack = vec![&root];
+        while let Some(id) = stack.pop() {
+            let Some(node) = nodes.get(id) else {
+                continue;
+            };
+            for dep in &node.deps {
+                let normal_or_build = dep
+                    .dep_kinds
+                    .iter()
+                    .any(|kind| kind.kind != DependencyKind::Development);
+                if normal_or_build && seen.insert(&dep.pkg) {
+                    stack.push(&dep.pkg);
+                }
+            }
+        }
+        let count = seen.len();
+        Some((root, count))
+    }
+
+    fn classify_failure(
+        &self,
+        status: ExitStatus,
+        diagnostics: Vec<Diagnostic>,
+        stderr: String,
+    ) -> BuildCrateError {
+        if diagnostics
+            .iter()
+            .any(|diag| matches!(diag.level, DiagnosticLevel::Error | DiagnosticLevel::Ice))
+        {
+            return BuildCrateError::CompileFailed {
+                diagnostics,
+                stderr,
+            };
+        }
+        // rustup: "error: toolchain 'nightly' is not installed"
+        if let Some(line) = stderr
+            .lines()
+            .find(|line| line.contains("toolchain") && line.contains("is not installed"))
+        {
+            return BuildCrateError::ToolchainNotFound {
+                toolchain: self.toolchain.clone(),
+                message: line.trim_start_matches("error: ").to_string(),
+            };
+        }
+        if stderr.contains("could not find `Cargo.toml`") {
+            return BuildCrateError::ManifestNotFound(self.manifest_path.clone());
+        }
+        BuildCrateError::Cargo { status, stderr }
+    }
+
+    /// `<target-dir>/[<target>/]doc/<lib name>.json`
+    fn json_path(&self, metadata: &Metadata) -> Result<PathBuf, BuildCrateError> {
+        let package = match &self.package {
+            Some(name) => metadata
+                .packages
+                .iter()
+                .find(|package| &package.name == name),
+            None => metadata.root_package(),
+        };
+        let lib_name = package
+            .and_then(|package| package.targets.iter().find(|target| is_library(target)))
+            .map(|target| target.name.replace('-', "_"))
+            .ok_or_else(|| {
+                io::Error::other(format!(
+                    "no library target found in {}",
+                    self.manifest_path.display()
+                ))
+            })?;
+        let mut path = match &self.target_dir {
+            Some(target_dir) => target_dir.clone(),
+            None => metadata.target_directory.clone().into_std_path_buf(),
+        };
+        if let Some(target) = &self.target {
+            path.push(target);
+        }
+        path.push("doc");
+        path.push(format!("{lib_name}.json"));
+        Ok(path)
+    }
+}
+
+/// The crate name of cargo's `Documenting` status line, e.g. `   Documenting my_crate v0.1.0 (/path/to/my_crate)`.
+/// The status may be colored.
+fn documenting(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("Documenting")?;
+    let rest = rest.strip_prefix("\x1b[0m").unwrap_or(rest);
+    rest.strip_prefix(' ')?.split_whitespace().next()
+}
@@ -0,0 +1,266 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use cargo_metadata::Metadata;
+use sha2::{Digest, Sha256};
+
+use crate::util::update;
+use crate::{BuildCrateError, CrateBuilder};
+
+impl CrateBuilder {
+    /// A fingerprint of everything the rustdoc JSON depends on: the sources of the local packages
+    /// (the workspace members and path dependencies), `Cargo.lock`, the cargo configuration (`.cargo/config.toml`),
+    /// the selected package, features and target, the rustdoc options, the `RUSTFLAGS` and `RUSTDOCFLAGS` environment
+    /// variables and the toolchain version.
+    ///
+    /// This is the key of the build cache (see [`CrateBuilder::cache_dir`]).
+    pub fn fingerprint(&self) -> Result<String, BuildCrateError> {
+        let metadata = self.metadata_command().exec()?;
+        self.fingerprint_with(&metadata, &self.toolchain_version()?)
+    }
+
+    /// [`CrateBuilder::fingerprint`] with the output of `cargo metadata` and [`CrateBuilder::toolchain_version`].
+    fn fingerprint_with(
+        &self,
+        metadata: &Metadata,
+        toolchain_version: &[u8],
+    ) -> Result<String, BuildCrateError> {
+        let mut hasher = Sha256::new();
+        update(&mut hasher, b"crate-inspector build cache v1");
+        update(&mut hasher, toolchain_version);
+
+        update(
+            &mut hasher,
+            self.package.as_deref().unwrap_or_default().as_bytes(),
+        );
+        update(
+            &mut hasher,
+            self.target.as_deref().unwrap_or_default().as_bytes(),
+        );
+        let mut features = self.features.clone();
+        features.sort();
+        features.dedup();
+        update(&mut hasher, features.join(",").as_bytes());
+        let flags = [
+            self.all_features,
+            self.no_default_features,
+            self.document_private_items,
+        ];
+        update(&mut hasher, &flags.map(u8::from));
+        update(
+            &mut hasher,
+            self.cap_lints.as_deref().unwrap_or_default().as_bytes(),
+        );
+        update(&mut hasher, &[u8::from(self.document_hidden_items)]);
+        let cargo_path = self.cargo_path.as_deref().unwrap_or(Path::new(""));
+        update(&mut hasher, cargo_path.as_os_str().as_encoded_bytes());
+        let vendor_dir = self.vendor_dir.as_deref().unwrap_or(Path::new(""));
+        update(&mut hasher, vendor_dir.as_os_str().as_encoded_bytes());
+        update(&mut hasher, self.cfgs.join("\x1f").as_bytes());
+        update(&mut hasher, self.rustdoc_flags.join("\x1f").as_bytes());
+        for (key, value) in &self.envs {
+            update(&mut hasher, key.as_encoded_bytes());
+            update(&mut hasher, value.as_encoded_bytes());
+        }
+        for key in FLAGS_ENVS {
+            let value = self.env_value(key).unwrap_or_default();
+            update(&mut hasher, value.as_encoded_bytes());
+        }
+
+        let lockfile = metadata.workspace_root.join("Cargo.lock");
+        update(&mut hasher, &fs::read(lockfile).unwrap_or_default());
+        for config in cargo_configs(metadata.workspace_root.as_std_path()) {
+            update(&mut hasher, config.as_os_str().as_encoded_bytes());
+            update(&mut hasher, &fs::read(&config)?);
+        }
+
+        let (roots, skip) = self.source_roots(metadata);
+        for root in roots {
+            hash_dir(&mut hasher, &root, &root, &skip)?;
+        }
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect())
+    }
+
+    /// The directories of the local packages (the workspace members and path dependencies) and of their targets outside
+    /// them (e.g. the file of [`CrateBuilder::from_file`]), and the directories under them that are not sources: the
+    /// target directories and the cache directory.
+    pub(crate) fn source_roots(&self, metadata: &Metadata) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let skip = [
+            Some(metadata.target_directory.clone().into_std_path_buf()),
+            self.target_dir.clone(),
+            self.cache_dir.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|dir| std::path::absolute(&dir).unwrap_or(dir))
+        .collect();
+        let mut roots = vec![];
+        for package in metadata
+            .packages
+            .iter()
+            .filter(|package| package.source.is_none())
+        {
+            let Some(package_dir) = package.manifest_path.parent() else {
+                continue;
+            };
+            roots.push(package_dir.as_std_path().to_path_buf());
+            roots.extend(
+                package
+                    .targets
+                    .iter()
+                    .filter(|target| !target.src_path.starts_with(package_dir))
+                    .filter_map(|target| target.src_path.parent())
+                    .map(|dir| dir.as_std_path().to_path_buf()),
+            );
+        }
+        roots.sort();
+        roots.dedup();
+        (roots, skip)
+    }
+
+    /// `cargo -vV` and `rustc -vV` of the toolchain, which include the commit hashes and the host triple.
+    fn toolchain_version(&self) -> Result<Vec<u8>, BuildCrateError> {
+        let mut version = self.version(self.cargo())?;
+        // The rustc cargo runs: `$RUSTC`, or the `rustc` next to a toolchain's cargo, or `rustc` through rustup
+        let rustc = match (self.env_value("RUSTC"), &self.cargo_path) {
+            (Some(rustc), _) => Some(PathBuf::from(rustc)),
+            (None, Some(cargo_path)) => {
+                Some(cargo_path.with_file_name(format!("rustc{}", std::env::consts::EXE_SUFFIX)))
+                    .filter(|rustc| rustc.is_file())
+            }
+            (None, None) => None,
+        };
+        let mut command = match (rustc, &self.toolchain) {
+            (Some(rustc), _) => Command::new(rustc),
+            (None, Some(toolchain)) => {
+                let mut command = Command::new("rustup");
+                command.args(["run", toolchain, "rustc"]);
+                command
+            }
+            (None, None) => Command::new("rustc"),
+        };
+        if let Some(toolchain) = &self.toolchain {
+            command.env("RUSTUP_TOOLCHAIN", toolchain);
+        }
+        command.envs(self.envs.iter().map(|(key, value)| (key, value)));
+        version.extend(self.version(command)?);
+        Ok(version)
+    }
+
+    fn version(&self, mut command: Command) -> Result<Vec<u8>, BuildCrateError> {
+        let output = command.arg("-vV").output()?;
+        if !output.status.success() {
+            return Err(BuildCrateError::ToolchainNotFound {
+                toolchain: self.toolchain.clone(),
+                message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        Ok(output.stdout)
+    }
+
+    /// The value of the environment variable `key` for cargo: set by [`CrateBuilder::env`] or inherited.
+    fn env_value(&self, key: &str) -> Option<OsString> {
+        match self.envs.iter().rev().find(|(name, _)| name == key) {
+            Some((_, value)) => Some(value.clone()),
+            None => std::env::var_os(key),
+        }
+    }
+
+    /// Like [`CrateBuilder::run`], but returns the cached JSON if the fingerprint is unchanged.
+    pub(crate) fn run_cached(
+        &self,
+        output: Option<(&mut dyn Write, &mut dyn Write)>,
+    ) -> Result<PathBuf, BuildCrateError> {
+        let Some(cache_dir) = &self.cache_dir else {
+            return self.run(output, None);
+        };
+        let metadata = self.metadata_command().exec()?;
+        let toolchain_version = self.toolchain_version()?;
+        let fingerprint = self.fingerprint_with(&metadata, &toolchain_version)?;
+        let cached = cache_dir.join(format!("{fingerprint}.json"));
+        if cached.is_file() {
+            self.emit(crate::progress::BuildEvent::Cached {
+                path: cached.clone(),
+            });
+            return Ok(cached);
+        }
+        let path = self.run(output, Some(&metadata))?;
+        // The sources changed during the build: the JSON may be of either version, so it is not cached
+        if self.fingerprint_with(&metadata, &toolchain_version)? != fingerprint {
+            return Ok(path);
+        }
+        fs::create_dir_all(cache_dir)?;
+        // Copy then rename so that concurrent builds never see a partially written file
+        let tmp = cached.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::copy(&path, &tmp)?;
+        fs::rename(&tmp, &cached)?;
+        Ok(cached)
+    }
+}
+
+/// Environment variables with compiler flags that cargo passes on, besides those set with [`CrateBuilder::env`].
+const FLAGS_ENVS: [&str; 4] = [
+    "RUSTFLAGS",
+    "CARGO_ENCODED_RUSTFLAGS",
+    "RUSTDOCFLAGS",
+    "CARGO_ENCODED_RUSTDOCFLAGS",
+];
+
+/// The cargo configuration files cargo reads when run in the current directory for a workspace at `workspace_root`:
+/// `.cargo/config.toml` (or `.cargo/config`) in their ancestors and in `$CARGO_HOME`.
+fn cargo_configs(workspace_root: &Path) -> Vec<PathBuf> {
+    let current_dir = std::env::current_dir().ok();
+    let mut dirs = current_dir
+        .iter()
+        .flat_map(|dir| dir.ancestors())
+        .chain(workspace_root.ancestors())
+        .map(|dir| dir.join(".cargo"))
+        .collect::<Vec<_>>();
+    let cargo_home = std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::home_dir().map(|home| home.join(".cargo")));
+    dirs.extend(cargo_home);
+    dirs.sort();
+    dirs.dedup();
+    dirs.iter()
+        .flat_map(|dir| [dir.join("config.toml"), dir.join("config")])
+        .filter(|config| config.is_file())
+        .collect()
+}
+
+/// Hashes the relative paths and contents of the files under `dir`, skipping hidden entries (e.g. `.git`)
+/// except `.cargo`.
+fn hash_dir(
+    hasher: &mut Sha256,
+    root: &Path,
+    dir: &Path,
+    skip: &[PathBuf],
+) -> Result<(), BuildCrateError> {
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name();
+        if (name.to_string_lossy().starts_with('.') && name != ".cargo")
+            || skip.iter().any(|skip| path.starts_with(skip))
+        {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            hash_dir(hasher, root, &path, skip)?;
+        } else if file_type.is_file() {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            update(hasher, relative.to_string_lossy().as_bytes());
+            update(hasher, &fs::read(&path)?);
+        }
+    }
+    Ok(())
+}
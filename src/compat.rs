@@ -0,0 +1,277 @@
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::BuildCrateError;
+
+/// The format version of `rustdoc-types` this crate is built against.
+pub use rustdoc_types::FORMAT_VERSION;
+
+/// The oldest format version that can be upgraded to [`FORMAT_VERSION`].
+pub const MIN_FORMAT_VERSION: u32 = 43;
+
+/// Whether JSON of `version` is known to be loadable.
+pub fn is_supported(version: u32) -> bool {
+    (MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version)
+}
+
+#[derive(Deserialize)]
+struct Header {
+    format_version: u32,
+}
+
+/// Read only the `format_version` field of rustdoc JSON.
+pub fn format_version(json: &[u8]) -> Result<u32, serde_json::Error> {
+    serde_json::from_slice::<Header>(json).map(|header| header.format_version)
+}
+
+/// How to load rustdoc JSON of a format version newer than [`FORMAT_VERSION`], e.g. built by a later nightly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NewerVersions {
+    /// Load it if it deserializes, and fail with [`BuildCrateError::FormatVersion`] (with the deserialization error
+    /// as its source) otherwise.
+    #[default]
+    Try,
+    /// Always fail with [`BuildCrateError::FormatVersion`]: such JSON may deserialize while meaning something else.
+    Reject,
+}
+
+/// Deserialize rustdoc JSON, upgrading older formats supported by this module.
+///
+/// Newer formats are loaded according to `newer`. Older unsupported formats are loaded if they happen to deserialize.
+pub(crate) fn deserialize(
+    json: &[u8],
+    newer: NewerVersions,
+) -> Result<rustdoc_types::Crate, BuildCrateError> {
+    let version = format_version(json)?;
+    if version < MIN_FORMAT_VERSION {
+        return serde_json::from_slice(json).map_err(|err| unsupported(version, Some(err)));
+    }
+    if version < FORMAT_VERSION {
+        let mut value = serde_json::from_slice::<Value>(json)?;
+        upgrade(&mut value, version);
+        return Ok(serde_json::from_value(value)?);
+    }
+    check_newer(version, newer)?;
+    let fail = |err| {
+        if version > FORMAT_VERSION {
+            unsupported(version, Some(err))
+        } else {
+            err.into()
+        }
+    };
+    serde_json::from_slice(json).or_else(|err| {
+        // v55: `#[macro_use]` became `Attribute::MacroUse`, which `rustdoc-types` 0.55.0 lacks
+        let mut value = serde_json::from_slice::<Value>(json).map_err(fail)?;
+        let mut found = false;
+        if let Some(Value::Object(index)) = value.get_mut("index") {
+            for item in index.values_mut() {
+                found |= downgrade_macro_use(item);
+            }
+        }
+        if !found {
+            return Err(fail(err));
+        }
+        serde_json::from_value(value).map_err(|_| fail(err))
+    })
+}
+
+/// Fail for versions newer than [`FORMAT_VERSION`] if they are to be rejected.
+pub(crate) fn check_newer(version: u32, newer: NewerVersions) -> Result<(), BuildCrateError> {
+    if version > FORMAT_VERSION && newer == NewerVersions::Reject {
+        return Err(unsupported(version, None));
+    }
+    Ok(())
+}
+
+pub(crate) fn unsupported(version: u32, source: Option<serde_json::Error>) -> BuildCrateError {
+    BuildCrateError::FormatVersion {
+        expected: FORMAT_VERSION,
+        actual: version,
+        source,
+    }
+}
+
+/// Upgrade rustdoc JSON of format `version` (at least [`MIN_FORMAT_VERSION`]) to [`FORMAT_VERSION`].
+pub fn upgrade(krate: &mut Value, version: u32) {
+    let Some(krate) = krate.as_object_mut() else {
+        return;
+    };
+    // v44: `Crate::target` was added
+    if version < 44 {
+        krate.entry("target").or_insert_with(|| {
+            json!({
+                "triple": "",
+                "target_features": [],
+            })
+        });
+    }
+    if let Some(Value::Object(index)) = krate.get_mut("index") {
+        for item in index.values_mut().filter_map(Value::as_object_mut) {
+            upgrade_item(item, version);
+        }
+    }
+    krate.insert("format_version".into(), FORMAT_VERSION.into());
+}
+
+fn upgrade_item(item: &mut Map<String, Value>, version: u32) {
+    // v45: span columns became one-indexed
+    if version < 45 {
+        if let Some(Value::Object(span)) = item.get_mut("span") {
+            for key in ["begin", "end"] {
+                if let Some(column) = span.get_mut(key).and_then(|pos| pos.get_mut(1)) {
+                    if let Some(n) = column.as_u64() {
+                        *column = (n + 1).into();
+                    }
+                }
+            }
+        }
+    }
+    // v54: `Item::attrs` changed from strings to `Attribute`
+    if version < 54 {
+        if let Some(Value::Array(attrs)) = item.get_mut("attrs") {
+            for attr in attrs.iter_mut() {
+                if let Value::String(s) = attr {
+                    *attr = parse_attr(s);
+                }
+            }
+        }
+    }
+}
+
+/// Deserialize an item of rustdoc JSON of (at least) [`FORMAT_VERSION`], e.g. for [`crate::lazy::LazyCrate`].
+pub(crate) fn deserialize_item(json: &str) -> Result<rustdoc_types::Item, serde_json::Error> {
+    serde_json::from_str(json).or_else(|err| {
+        let mut item = serde_json::from_str::<Value>(json)?;
+        if !downgrade_macro_use(&mut item) {
+            return Err(err);
+        }
+        serde_json::from_value(item).map_err(|_| err)
+    })
+}
+
+/// Replace `Attribute::MacroUse` in the attributes of `item` with the `Attribute::Other` of earlier versions.
+/// Returns whether there was any.
+fn downgrade_macro_use(item: &mut Value) -> bool {
+    let Some(Value::Array(attrs)) = item.get_mut("attrs") else {
+        return false;
+    };
+    let mut found = false;
+    for attr in attrs.iter_mut().filter(|attr| *attr == "macro_use") {
+        *attr = json!({ "other": "#[macro_use]" });
+        found = true;
+    }
+    found
+}
+
+/// Convert a stringified attribute (e.g. `#[repr(C, u8)]`) into the JSON of an [`rustdoc_types::Attribute`].
+fn parse_attr(attr: &str) -> Value {
+    let other = || json!({ "other": attr });
+    let Some(inner) = attr
+        .trim()
+        .strip_prefix("#[")
+        .and_then(|s| s.strip_suffix(']'))
+    else {
+        return other();
+    };
+    let inner = inner
+        .strip_prefix("unsafe(")
+        .and_then(|s| s.strip_suffix(')'))
+        .unwrap_or(inner)
+        .trim();
+    // v48..=v52 printed some attributes in their parsed form, e.g. `#[attr = MustUse {reason: "text"}]`
+    if let Some(parsed) = inner.strip_prefix("attr =") {
+        return parse_parsed_attr(parsed.trim()).unwrap_or_else(other);
+    }
+    match inner {
+        "non_exhaustive" => return json!("non_exhaustive"),
+        "automatically_derived" => return json!("automatically_derived"),
+        "macro_export" => return json!("macro_export"),
+        "no_mangle" => return json!("no_mangle"),
+        "must_use" => return json!({ "must_use": { "reason": null } }),
+        _ => {}
+    }
+    if let Some(reason) = inner.strip_prefix("must_use =").and_then(unquote) {
+        return json!({ "must_use": { "reason": reason } });
+    }
+    if let Some(name) = inner.strip_prefix("export_name =").and_then(unquote) {
+        return json!({ "export_name": name });
+    }
+    if let Some(name) = inner.strip_prefix("link_section =").and_then(unquote) {
+        return json!({ "link_section": name });
+    }
+    if let Some(args) = inner
+        .strip_prefix("target_feature(")
+        .and_then(|s| s.strip_suffix(')'))
+    {
+        let enable = args
+            .split(',')
+            .filter_map(|arg| arg.trim().strip_prefix("enable =").and_then(unquote))
+            .collect::<Vec<_>>();
+        return json!({ "target_feature": { "enable": enable } });
+    }
+    if let Some(args) = inner
+        .strip_prefix("repr(")
+        .and_then(|s| s.strip_suffix(')'))
+    {
+        return parse_repr(args).unwrap_or_else(other);
+    }
+    other()
+}
+
+/// Convert the inner part of a `#[attr = ...]` attribute. Attributes without an [`rustdoc_types::Attribute`] variant
+/// of their own (`Inline(Hint)`, `Cold`, `Optimize(Size)`) stay as they are, which is how later versions print them.
+fn parse_parsed_attr(attr: &str) -> Option<Value> {
+    if attr == "MustUse" {
+        return Some(json!({ "must_use": { "reason": null } }));
+    }
+    let reason = attr
+        .strip_prefix("MustUse")?
+        .trim()
+        .strip_prefix('{')?
+        .strip_suffix('}')?
+        .trim()
+        .strip_prefix("reason:")?;
+    Some(json!({ "must_use": { "reason": unquote(reason)? } }))
+}
+
+fn parse_repr(args: &str) -> Option<Value> {
+    let mut kind = "rust";
+    let mut align = None;
+    let mut packed = None;
+    let mut int = None;
+    for arg in args.split(',').map(str::trim) {
+        match arg {
+            "C" => kind = "c",
+            "transparent" => kind = "transparent",
+            "simd" => kind = "simd",
+            "Rust" => kind = "rust",
+            "packed" => packed = Some(1),
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+            | "i128" | "isize" => int = Some(arg),
+            _ => {
+                if let Some(n) = arg.strip_prefix("align(").and_then(|s| s.strip_suffix(')')) {
+                    align = Some(n.parse::<u64>().ok()?);
+                } else if let Some(n) = arg
+                    .strip_prefix("packed(")
+                    .and_then(|s| s.strip_suffix(')'))
+                {
+                    packed = Some(n.parse::<u64>().ok()?);
+                } else {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(json!({
+        "repr": {
+            "kind": kind,
+            "align": align,
+            "packed": packed,
+            "int": int,
+        }
+    }))
+}
+
+fn unquote(s: &str) -> Option<String> {
+    serde_json::from_str(s.trim()).ok()
+}
@@ -0,0 +1,238 @@
+use std::fmt;
+
+use rustdoc_types::ItemKind;
+
+use crate::api::{merge_api_paths, MergedPath};
+use crate::{AnyItem, BuildCrateError, Crate, CrateBuilder};
+
+/// A combination of features to build a crate with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FeatureSet {
+    /// `--no-default-features`
+    None,
+    /// The default features.
+    Default,
+    /// `--all-features`
+    All,
+    /// `--no-default-features --features <feature>`
+    Only(String),
+}
+
+impl FeatureSet {
+    fn configure(&self, builder: CrateBuilder) -> CrateBuilder {
+        let builder = builder
+            .all_features(false)
+            .no_default_features(false)
+            .features(Vec::<String>::new());
+        match self {
+            FeatureSet::None => builder.no_default_features(true),
+            FeatureSet::Default => builder,
+            FeatureSet::All => builder.all_features(true),
+            FeatureSet::Only(feature) => builder.no_default_features(true).features([feature]),
+        }
+    }
+}
+
+impl fmt::Display for FeatureSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureSet::None => write!(f, "no features"),
+            FeatureSet::Default => write!(f, "default features"),
+            FeatureSet::All => write!(f, "all features"),
+            FeatureSet::Only(feature) => write!(f, "feature `{feature}`"),
+        }
+    }
+}
+
+/// Builds a crate once per feature combination and merges the results into a [`FeatureMatrix`].
+///
+/// By default, the crate is built with no features, the default features, all features, and each feature alone.
+///
+/// ```no_run
+/// use crate_inspector::CrateBuilder;
+/// use crate_inspector::features::FeatureMatrixBuilder;
+///
+/// let matrix = FeatureMatrixBuilder::default()
+///     .crate_builder(CrateBuilder::default().toolchain("nightly").silent(true))
+///     .build()
+///     .unwrap();
+/// for item in matrix.gated_items() {
+///     let sets = item.feature_sets().map(|set| set.to_string()).collect::<Vec<_>>();
+///     println!("{}: {}", item.path(), sets.join(", "));
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct FeatureMatrixBuilder {
+    builder: CrateBuilder,
+    sets: Option<Vec<FeatureSet>>,
+}
+
+impl FeatureMatrixBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The builder used as a template for each feature set (its feature options are overridden).
+    pub fn crate_builder(mut self, builder: CrateBuilder) -> Self {
+        self.builder = builder;
+        self
+    }
+
+    /// Build only the given feature sets instead of the default matrix.
+    pub fn sets(mut self, sets: impl IntoIterator<Item = FeatureSet>) -> Self {
+        self.sets = Some(sets.into_iter().collect());
+        self
+    }
+
+    /// The default matrix: no features, default, all, and each feature of the package alone.
+    pub fn default_sets(&self) -> Result<Vec<FeatureSet>, BuildCrateError> {
+        let metadata = self.builder.metadata_command().no_deps().exec()?;
+        let package = match &self.builder.package {
+            Some(name) => metadata
+                .packages
+                .iter()
+                .find(|package| &package.name == name),
+            None => metadata.root_package(),
+        };
+        let mut sets = vec![FeatureSet::None, FeatureSet::Default, FeatureSet::All];
+        sets.extend(
+            package
+                .iter()
+                .flat_map(|package| package.features.keys())
+                .filter(|feature| *feature != "default")
+                .map(|feature| FeatureSet::Only(feature.clone())),
+        );
+        Ok(sets)
+    }
+
+    /// Builds each feature set.
+    ///
+    /// Only failing to read the package is an error; build failures of individual sets are collected into [`FeatureMatrix::errors`].
+    pub fn build(self) -> Result<FeatureMatrix, BuildCrateError> {
+        let sets = match &self.sets {
+            Some(sets) => sets.clone(),
+            None => self.default_sets()?,
+        };
+        let mut crates = vec![];
+        let mut errors = vec![];
+        for set in sets {
+            match set.configure(self.builder.clone()).build() {
+                Ok(krate) => crates.push((set, krate)),
+                Err(err) => errors.push((set, err)),
+            }
+        }
+        let items = merge_api_paths(&crates.iter().map(|(_, krate)| krate).collect::<Vec<_>>());
+        Ok(FeatureMatrix {
+            crates,
+            errors,
+            items,
+        })
+    }
+}
+
+/// The builds of a crate with several feature sets, created by [`FeatureMatrixBuilder::build`].
+///
+/// Items are identified across the builds by their public path and kind (see [`MatrixItem`]).
+#[derive(Debug)]
+pub struct FeatureMatrix {
+    crates: Vec<(FeatureSet, Crate)>,
+    errors: Vec<(FeatureSet, BuildCrateError)>,
+    items: Vec<MergedPath>,
+}
+
+impl FeatureMatrix {
+    /// The feature sets that were built successfully.
+    pub fn feature_sets(&self) -> impl Iterator<Item = &FeatureSet> {
+        self.crates.iter().map(|(set, _)| set)
+    }
+
+    /// Get the build of a feature set.
+    pub fn get(&self, set: &FeatureSet) -> Option<&Crate> {
+        self.crates
+            .iter()
+            .find(|(built, _)| built == set)
+            .map(|(_, krate)| krate)
+    }
+
+    pub fn crates(&self) -> impl Iterator<Item = (&FeatureSet, &Crate)> {
+        self.crates.iter().map(|(set, krate)| (set, krate))
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = (&FeatureSet, &BuildCrateError)> {
+        self.errors.iter().map(|(set, err)| (set, err))
+    }
+
+    /// The public items of all builds, sorted by path.
+    pub fn items(&self) -> impl Iterator<Item = MatrixItem<'_>> {
+        self.items.iter().map(|merged| MatrixItem {
+            matrix: self,
+            merged,
+        })
+    }
+
+    /// Get an item by its path, e.g. `my_crate::foo::Bar` or `my_crate::Bar::method`.
+    pub fn get_item(&self, path: &str) -> Option<MatrixItem<'_>> {
+        self.items().find(|item| item.path() == path)
+    }
+
+    /// The items that are not exposed by every feature set.
+    pub fn gated_items(&self) -> impl Iterator<Item = MatrixItem<'_>> {
+        self.items().filter(|item| !item.is_always_exposed())
+    }
+}
+
+/// A public path of a [`FeatureMatrix`] and the feature sets that expose it.
+#[derive(Debug, Clone, Copy)]
+pub struct MatrixItem<'a> {
+    matrix: &'a FeatureMatrix,
+    merged: &'a MergedPath,
+}
+
+impl<'a> MatrixItem<'a> {
+    pub fn path(&self) -> &'a str {
+        &self.merged.path
+    }
+
+    pub fn kind(&self) -> ItemKind {
+        self.merged.kind
+    }
+
+    /// The feature sets whose build exposes the item.
+    pub fn feature_sets(&self) -> impl Iterator<Item = &'a FeatureSet> {
+        self.matrix
+            .crates
+            .iter()
+            .zip(&self.merged.ids)
+            .filter(|(_, id)| id.is_some())
+            .map(|((set, _), _)| set)
+    }
+
+    pub fn is_exposed_by(&self, set: &FeatureSet) -> bool {
+        self.feature_sets().any(|exposing| exposing == set)
+    }
+
+    /// Whether every feature set that was built exposes the item.
+    pub fn is_always_exposed(&self) -> bool {
+        self.merged.ids.iter().all(Option::is_some)
+    }
+
+    /// The item in the build of the first feature set that exposes it.
+    pub fn item(&self) -> AnyItem<'a> {
+        self.matrix
+            .crates
+            .iter()
+            .zip(&self.merged.ids)
+            .find_map(|((_, krate), id)| Some(krate.any_item(&krate.index[id.as_ref()?])))
+            .expect("a merged path is present in at least one build")
+    }
+
+    /// The item in the build of `set`, if exposed.
+    pub fn item_in(&self, set: &FeatureSet) -> Option<AnyItem<'a>> {
+        self.matrix
+            .crates
+            .iter()
+            .zip(&self.merged.ids)
+            .find(|((built, _), _)| built == set)
+            .and_then(|((_, krate), id)| Some(krate.any_item(&krate.index[id.as_ref()?])))
+    }
+}
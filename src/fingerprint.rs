@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use rustdoc_types::{
+    Attribute, GenericBound, Generics, Id, Item, ItemEnum, StructKind, VariantKind, Visibility,
+};
+use sha2::{Digest, Sha256};
+
+use crate::format::{bound_to_string, where_clause_to_string};
+use crate::stable_id::StableId;
+use crate::targets::signature;
+use crate::util::{update, visit_item_paths};
+use crate::{item_kind, Crate};
+
+/// Computes content fingerprints of items, created by [`Crate::fingerprinter`].
+///
+/// The fingerprint of an item is a hash of its kind, name, visibility and rendered signature (with canonical paths),
+/// its generics and where clause, its fields or variants (with their names, types and visibilities), and the
+/// `#[non_exhaustive]`, `#[repr]` and `#[must_use]` attributes. Docs are only included if [`Fingerprinter::docs`] is enabled.
+/// The items of modules, traits and impls are not included; they have their own fingerprints.
+///
+/// Together with [`StableId`], fingerprints tell which items changed between two builds of a crate.
+///
+/// ```no_run
+/// use crate_inspector::CrateBuilder;
+///
+/// let old = CrateBuilder::default().toolchain("nightly").build().unwrap();
+/// let old = old.fingerprinter().build();
+/// // ... edit the crate ...
+/// let new = CrateBuilder::default().toolchain("nightly").build().unwrap();
+/// let new = new.fingerprinter().build();
+/// for stable_id in new.changed_since(&old) {
+///     println!("changed: {stable_id}");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fingerprinter<'a> {
+    krate: &'a Crate,
+    docs: bool,
+}
+
+/// The fingerprints of the local items of a crate, keyed by their [`StableId`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fingerprints {
+    items: HashMap<StableId, String>,
+}
+
+impl Crate {
+    /// Compute content fingerprints of the items. See [`Fingerprinter`].
+    pub fn fingerprinter(&self) -> Fingerprinter<'_> {
+        Fingerprinter {
+            krate: self,
+            docs: false,
+        }
+    }
+}
+
+impl<'a> Fingerprinter<'a> {
+    /// Include the docs of the items (disabled by default).
+    pub fn docs(mut self, docs: bool) -> Self {
+        self.docs = docs;
+        self
+    }
+
+    /// The fingerprint of the item `id`, as a hex string. `None` if it is not in the index.
+    pub fn fingerprint(&self, id: &Id) -> Option<String> {
+        let item = self.krate.index.get(id)?;
+        let mut hasher = Sha256::new();
+        update(&mut hasher, b"crate-inspector item fingerprint v1");
+        self.hash_item(&mut hasher, item);
+        self.hash_members(&mut hasher, item);
+        Some(
+            hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+        )
+    }
+
+    /// The fingerprints of all local items that have a [`StableId`].
+    pub fn build(&self) -> Fingerprints {
+        let items = self
+            .krate
+            .items()
+            .filter_map(|item| {
+                let stable_id = self.krate.stable_id(&item.id)?;
+                Some((stable_id, self.fingerprint(&item.id)?))
+            })
+            .collect();
+        Fingerprints { items }
+    }
+
+    /// Hash the item itself, without its fields or variants.
+    fn hash_item(&self, hasher: &mut Sha256, item: &Item) {
+        let mut item = item.clone();
+        visit_item_paths(&mut item, |path| self.krate.canonicalize(path));
+
+        update(hasher, format!("{:?}", item_kind(&item)).as_bytes());
+        update(hasher, item.name.as_deref().unwrap_or_default().as_bytes());
+        let visibility = match &item.visibility {
+            Visibility::Public => "pub".to_string(),
+            Visibility::Default => String::new(),
+            Visibility::Crate => "pub(crate)".to_string(),
+            Visibility::Restricted { path, .. } => format!("pub(in {path})"),
+        };
+        update(hasher, visibility.as_bytes());
+        update(hasher, signature(&item).as_bytes());
+        if let Some(generics) = generics(&item) {
+            update(hasher, where_clause_to_string(generics).as_bytes());
+        }
+        let (flags, bounds): (&[bool], &[GenericBound]) = match &item.inner {
+            ItemEnum::Struct(struct_) => match &struct_.kind {
+                StructKind::Unit => (&[false, false], &[]),
+                StructKind::Tuple(_) => (&[true, false], &[]),
+                StructKind::Plain {
+                    has_stripped_fields,
+                    ..
+                } => (&[false, *has_stripped_fields], &[]),
+            },
+            ItemEnum::Union(union) => (&[union.has_stripped_fields], &[]),
+            ItemEnum::Enum(enum_) => (&[enum_.has_stripped_variants], &[]),
+            ItemEnum::Variant(variant) => match &variant.kind {
+                VariantKind::Plain => (&[false, false], &[]),
+                VariantKind::Tuple(_) => (&[true, false], &[]),
+                VariantKind::Struct {
+                    has_stripped_fields,
+                    ..
+                } => (&[false, *has_stripped_fields], &[]),
+            },
+            ItemEnum::Trait(trait_) => (&[trait_.is_auto, trait_.is_unsafe], &trait_.bounds),
+            ItemEnum::TraitAlias(alias) => (&[], &alias.params),
+            ItemEnum::Impl(impl_) => (&[impl_.is_unsafe, impl_.is_negative], &[]),
+            ItemEnum::AssocType { bounds, .. } => (&[], bounds),
+            _ => (&[], &[]),
+        };
+        update(
+            hasher,
+            &flags.iter().map(|flag| u8::from(*flag)).collect::<Vec<_>>(),
+        );
+        let bounds = bounds.iter().map(bound_to_string).collect::<Vec<_>>();
+        update(hasher, bounds.join(" + ").as_bytes());
+        if let ItemEnum::Impl(impl_) = &item.inner {
+            let key = self
+                .krate
+                .stable_id(&item.id)
+                .and_then(|id| id.impl_().cloned());
+            update(
+                hasher,
+                key.map(|key| key.to_string())
+                    .unwrap_or_default()
+                    .as_bytes(),
+            );
+            update(hasher, impl_.provided_trait_methods.join(",").as_bytes());
+        }
+        for attr in &item.attrs {
+            if matches!(
+                attr,
+                Attribute::NonExhaustive | Attribute::MustUse { .. } | Attribute::Repr(_)
+            ) {
+                update(hasher, format!("{attr:?}").as_bytes());
+            }
+        }
+        if self.docs {
+            update(hasher, item.docs.as_deref().unwrap_or_default().as_bytes());
+        }
+    }
+
+    /// Hash the fields of a struct, union or variant, or the variants of an enum (with their fields).
+    fn hash_members(&self, hasher: &mut Sha256, item: &Item) {
+        for member in members(item) {
+            match member.and_then(|id| self.krate.index.get(&id)) {
+                Some(member) => {
+                    self.hash_item(hasher, member);
+                    self.hash_members(hasher, member);
+                }
+                None => update(hasher, b"stripped"),
+            }
+        }
+    }
+}
+
+/// The fields of a struct, union or variant, or the variants of an enum. Stripped tuple fields are `None`.
+fn members(item: &Item) -> Vec<Option<Id>> {
+    match &item.inner {
+        ItemEnum::Struct(struct_) => match &struct_.kind {
+            StructKind::Unit => vec![],
+            StructKind::Tuple(fields) => fields.clone(),
+            StructKind::Plain { fields, .. } => fields.iter().copied().map(Some).collect(),
+        },
+        ItemEnum::Union(union) => union.fields.iter().copied().map(Some).collect(),
+        ItemEnum::Enum(enum_) => enum_.variants.iter().copied().map(Some).collect(),
+        ItemEnum::Variant(variant) => match &variant.kind {
+            VariantKind::Plain => vec![],
+            VariantKind::Tuple(fields) => fields.clone(),
+            VariantKind::Struct { fields, .. } => fields.iter().copied().map(Some).collect(),
+        },
+        _ => vec![],
+    }
+}
+
+fn generics(item: &Item) -> Option<&Generics> {
+    match &item.inner {
+        ItemEnum::Union(union) => Some(&union.generics),
+        ItemEnum::Struct(struct_) => Some(&struct_.generics),
+        ItemEnum::Enum(enum_) => Some(&enum_.generics),
+        ItemEnum::Function(function) => Some(&function.generics),
+        ItemEnum::Trait(trait_) => Some(&trait_.generics),
+        ItemEnum::TraitAlias(alias) => Some(&alias.generics),
+        ItemEnum::Impl(impl_) => Some(&impl_.generics),
+        ItemEnum::TypeAlias(alias) => Some(&alias.generics),
+        ItemEnum::AssocType { generics, .. } => Some(generics),
+        _ => None,
+    }
+}
+
+impl Fingerprints {
+    pub fn get(&self, stable_id: &StableId) -> Option<&str> {
+        self.items.get(stable_id).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&StableId, &str)> {
+        self.items
+            .iter()
+            .map(|(stable_id, fingerprint)| (stable_id, fingerprint.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Items that were added since `old`, or whose fingerprint differs.
+    pub fn changed_since<'b>(
+        &'b self,
+        old: &'b Fingerprints,
+    ) -> impl Iterator<Item = &'b StableId> {
+        self.items
+            .iter()
+            .filter(|(stable_id, fingerprint)| old.items.get(*stable_id) != Some(fingerprint))
+            .map(|(stable_id, _)| stable_id)
+    }
+
+    /// Items of `old` that no longer exist.
+    pub fn removed_since<'b>(
+        &'b self,
+        old: &'b Fingerprints,
+    ) -> impl Iterator<Item = &'b StableId> {
+        old.items
+            .keys()
+            .filter(|stable_id| !self.items.contains_key(*stable_id))
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::HashMap;
+
+use rustdoc_types::{
+    Abi, Constant, Enum, Function, FunctionHeader, FunctionSignature, GenericBound, Generics, Id,
+    Impl, Item, ItemEnum, ItemSummary, Module, Path, Static, Struct, StructKind, Target, Trait,
+    TraitAlias, Type, TypeAlias, Union, Use, Variant, VariantKind, Visibility,
+};
+
+use crate::compat::FORMAT_VERSION;
+use crate::{item_kind, Crate};
+
+/// Builds a [`Crate`] in memory, for testing code that inspects crates without running rustdoc.
+///
+/// Each method adds an item, wires it into its parent (module items, fields, variants, impls, ...) and the `paths`
+/// of the crate, and returns its id. Items of a module are public; the methods panic if the parent id has the wrong kind.
+///
+/// ```
+/// use crate_inspector::fixture::CrateFixture;
+/// use rustdoc_types::Type;
+///
+/// let mut fixture = CrateFixture::new("my_crate");
+/// let inner = fixture.module(fixture.root(), "inner");
+/// let foo = fixture.struct_(inner, "Foo", [("x", Type::Primitive("u8".to_string()))]);
+/// let impl_ = fixture.impl_(foo, None);
+/// fixture.method(impl_, "get", [("self", Type::Generic("Self".to_string()))], Some(Type::Primitive("u8".to_string())));
+/// let krate = fixture.build();
+///
+/// let inner = krate.get_module("inner").unwrap();
+/// let foo = inner.get_struct("Foo").unwrap();
+/// assert_eq!(foo.fields().unwrap().count(), 1);
+/// assert!(krate.get_item_by_path("my_crate::inner::Foo").is_some());
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrateFixture {
+    krate: rustdoc_types::Crate,
+    next_id: u32,
+}
+
+impl CrateFixture {
+    /// A crate with an empty root module named `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let mut fixture = Self {
+            krate: rustdoc_types::Crate {
+                root: Id(0),
+                crate_version: None,
+                includes_private: false,
+                index: HashMap::new(),
+                paths: HashMap::new(),
+                external_crates: HashMap::new(),
+                target: Target {
+                    triple: String::new(),
+                    target_features: vec![],
+                },
+                format_version: FORMAT_VERSION,
+            },
+            next_id: 0,
+        };
+        let root = fixture.add(
+            Some(name.clone()),
+            Visibility::Public,
+            ItemEnum::Module(Module {
+                is_crate: true,
+                items: vec![],
+                is_stripped: false,
+            }),
+        );
+        fixture.add_path(root, vec![name]);
+        fixture
+    }
+
+    pub fn crate_version(mut self, version: impl Into<String>) -> Self {
+        self.krate.crate_version = Some(version.into());
+        self
+    }
+
+    /// The id of the root module.
+    pub fn root(&self) -> Id {
+        self.krate.root
+    }
+
+    /// Get an item added to the fixture, e.g. to modify its docs or attributes.
+    pub fn item_mut(&mut self, id: Id) -> &mut Item {
+        self.krate.index.get_mut(&id).expect("no such item")
+    }
+
+    /// Add a public item to the module `parent`.
+    pub fn item(&mut self, parent: Id, name: &str, inner: ItemEnum) -> Id {
+        let id = self.add(Some(name.to_string()), Visibility::Public, inner);
+        let ItemEnum::Module(module) = &mut self.item_mut(parent).inner else {
+            panic!("{parent:?} is not a module");
+        };
+        module.items.push(id);
+        let mut path = self.krate.paths[&parent].path.clone();
+        path.push(name.to_string());
+        self.add_path(id, path);
+        id
+    }
+
+    pub fn module(&mut self, parent: Id, name: &str) -> Id {
+        self.item(
+            parent,
+            name,
+            ItemEnum::Module(Module {
+                is_crate: false,
+                items: vec![],
+                is_stripped: false,
+            }),
+        )
+    }
+
+    /// A struct with named public fields.
+    pub fn struct_<'a>(
+        &mut self,
+        parent: Id,
+        name: &str,
+        fields: impl IntoIterator<Item = (&'a str, Type)>,
+    ) -> Id {
+        let fields = self.fields(fields);
+        self.item(
+            parent,
+            name,
+            ItemEnum::Struct(Struct {
+                kind: StructKind::Plain {
+                    fields,
+                    has_stripped_fields: false,
+                },
+                generics: empty_generics(),
+                impls: vec![],
+            }),
+        )
+    }
+
+    /// A union with named public fields.
+    pub fn union_<'a>(
+        &mut self,
+        parent: Id,
+        name: &str,
+        fields: impl IntoIterator<Item = (&'a str, Type)>,
+    ) -> Id {
+        let fields = self.fields(fields);
+        self.item(
+            parent,
+            name,
+            ItemEnum::Union(Union {
+                generics: empty_generics(),
+                has_stripped_fields: false,
+                fields,
+                impls: vec![],
+            }),
+        )
+    }
+
+    /// An enum with unit variants.
+    pub fn enum_<'a>(
+        &mut self,
+        parent: Id,
+        name: &str,
+        variants: impl IntoIterator<Item = &'a str>,
+    ) -> Id {
+        let variants = variants
+            .into_iter()
+            .map(|variant| {
+                self.add(
+                    Some(variant.to_string()),
+                    Visibility::Default,
+                    ItemEnum::Variant(Variant {
+                        kind: VariantKind::Plain,
+                        discriminant: None,
+                    }),
+                )
+            })
+            .collect();
+        self.item(
+            parent,
+            name,
+            ItemEnum::Enum(Enum {
+                generics: empty_generics(),
+                has_stripped_variants: false,
+                variants,
+                impls: vec![],
+            }),
+        )
+    }
+
+    pub fn function<'a>(
+        &mut self,
+        parent: Id,
+        name: &str,
+        inputs: impl IntoIterator<Item = (&'a str, Type)>,
+        output: Option<Type>,
+    ) -> Id {
+        self.item(parent, name, function(inputs, output, true))
+    }
+
+    pub fn constant(&mut self, parent: Id, name: &str, type_: Type, expr: &str) -> Id {
+        self.item(
+            parent,
+            name,
+            ItemEnum::Constant {
+                type_,
+                const_: Constant {
+                    expr: expr.to_string(),
+                    value: None,
+                    is_literal: true,
+                },
+            },
+        )
+    }
+
+    pub fn static_(&mut self, parent: Id, name: &str, type_: Type) -> Id {
+        self.item(
+            parent,
+            name,
+            ItemEnum::Static(Static {
+                type_,
+                is_mutable: false,
+                expr: String::new(),
+                is_unsafe: false,
+            }),
+        )
+    }
+
+    pub fn type_alias(&mut self, parent: Id, name: &str, type_: Type) -> Id {
+        self.item(
+            parent,
+            name,
+            ItemEnum::TypeAlias(TypeAlias {
+                type_,
+                generics: empty_generics(),
+            }),
+        )
+    }
+
+    /// A trait without items; add methods with [`CrateFixture::trait_method`].
+    pub fn trait_(&mut self, parent: Id, name: &str) -> Id {
+        self.item(
+            parent,
+            name,
+            ItemEnum::Trait(Trait {
+                is_auto: false,
+                is_unsafe: false,
+                is_dyn_compatible: true,
+                items: vec![],
+                generics: empty_generics(),
+                bounds: vec![],
+                implementations: vec![],
+            }),
+        )
+    }
+
+    /// A required method of the trait `trait_`.
+    pub fn trait_method<'a>(
+        &mut self,
+        trait_: Id,
+        name: &str,
+        inputs: impl IntoIterator<Item = (&'a str, Type)>,
+        output: Option<Type>,
+    ) -> Id {
+        let id = self.add(
+            Some(name.to_string()),
+            Visibility::Default,
+            function(inputs, output, false),
+        );
+        let ItemEnum::Trait(trait_item) = &mut self.item_mut(trait_).inner else {
+            panic!("{trait_:?} is not a trait");
+        };
+        trait_item.items.push(id);
+        id
+    }
+
+    pub fn trait_alias(&mut self, parent: Id, name: &str, bounds: Vec<GenericBound>) -> Id {
+        self.item(
+            parent,
+            name,
+            ItemEnum::TraitAlias(TraitAlias {
+                generics: empty_generics(),
+                params: bounds,
+            }),
+        )
+    }
+
+    pub fn macro_(&mut self, parent: Id, name: &str, source: &str) -> Id {
+        self.item(parent, name, ItemEnum::Macro(source.to_string()))
+    }
+
+    /// A `pub use` of `target` in the module `parent`.
+    pub fn use_(&mut self, parent: Id, target: Id) -> Id {
+        let name = self.krate.index[&target].name.clone().unwrap_or_default();
+        let source = self.krate.paths[&target].path.join("::");
+        let id = self.add(
+            None,
+            Visibility::Public,
+            ItemEnum::Use(Use {
+                source,
+                name,
+                id: Some(target),
+                is_glob: false,
+            }),
+        );
+        let ItemEnum::Module(module) = &mut self.item_mut(parent).inner else {
+            panic!("{parent:?} is not a module");
+        };
+        module.items.push(id);
+        id
+    }
+
+    /// An impl for the struct, enum or union `for_`: inherent if `trait_` is `None`, or of the trait `trait_`.
+    pub fn impl_(&mut self, for_: Id, trait_: Option<Id>) -> Id {
+        let trait_path = trait_.map(|trait_| self.path(trait_));
+        let id = self.add(
+            None,
+            Visibility::Default,
+            ItemEnum::Impl(Impl {
+                is_unsafe: false,
+                generics: empty_generics(),
+                provided_trait_methods: vec![],
+                trait_: trait_path,
+                for_: self.resolved(for_),
+                items: vec![],
+                is_negative: false,
+                is_synthetic: false,
+                blanket_impl: None,
+            }),
+        );
+        let impls = match &mut self.item_mut(for_).inner {
+            ItemEnum::Struct(struct_) => &mut struct_.impls,
+            ItemEnum::Enum(enum_) => &mut enum_.impls,
+            ItemEnum::Union(union) => &mut union.impls,
+            _ => panic!("{for_:?} is not a struct, enum or union"),
+        };
+        impls.push(id);
+        if let Some(trait_) = trait_ {
+            let ItemEnum::Trait(trait_item) = &mut self.item_mut(trait_).inner else {
+                panic!("{trait_:?} is not a trait");
+            };
+            trait_item.implementations.push(id);
+        }
+        id
+    }
+
+    /// A method of the impl `impl_`, public if the impl is inherent.
+    pub fn method<'a>(
+        &mut self,
+        impl_: Id,
+        name: &str,
+        inputs: impl IntoIterator<Item = (&'a str, Type)>,
+        output: Option<Type>,
+    ) -> Id {
+        let ItemEnum::Impl(impl_item) = &self.krate.index[&impl_].inner else {
+            panic!("{impl_:?} is not an impl");
+        };
+        let visibility = match impl_item.trait_ {
+            Some(_) => Visibility::Default,
+            None => Visibility::Public,
+        };
+        let id = self.add(
+            Some(name.to_string()),
+            visibility,
+            function(inputs, output, true),
+        );
+        let ItemEnum::Impl(impl_item) = &mut self.item_mut(impl_).inner else {
+            unreachable!()
+        };
+        impl_item.items.push(id);
+        id
+    }
+
+    /// A path to an item, e.g. for the trait of a bound.
+    pub fn path(&self, id: Id) -> Path {
+        Path {
+            path: self.krate.index[&id].name.clone().unwrap_or_default(),
+            id,
+            args: None,
+        }
+    }
+
+    /// The type of the struct, enum, union or type alias `id`.
+    pub fn resolved(&self, id: Id) -> Type {
+        Type::ResolvedPath(self.path(id))
+    }
+
+    pub fn build(self) -> Crate {
+        Crate::new(self.krate)
+    }
+
+    fn add(&mut self, name: Option<String>, visibility: Visibility, inner: ItemEnum) -> Id {
+        let id = Id(self.next_id);
+        self.next_id += 1;
+        let item = Item {
+            id,
+            crate_id: 0,
+            name,
+            span: None,
+            visibility,
+            docs: None,
+            links: HashMap::new(),
+            attrs: vec![],
+            deprecation: None,
+            inner,
+        };
+        self.krate.index.insert(id, item);
+        id
+    }
+
+    fn add_path(&mut self, id: Id, path: Vec<String>) {
+        let kind = item_kind(&self.krate.index[&id]);
+        let summary = ItemSummary {
+            crate_id: 0,
+            path,
+            kind,
+        };
+        self.krate.paths.insert(id, summary);
+    }
+
+    fn fields<'a>(&mut self, fields: impl IntoIterator<Item = (&'a str, Type)>) -> Vec<Id> {
+        fields
+            .into_iter()
+            .map(|(name, type_)| {
+                self.add(
+                    Some(name.to_string()),
+                    Visibility::Public,
+                    ItemEnum::StructField(type_),
+                )
+            })
+            .collect()
+    }
+}
+
+fn empty_generics() -> Generics {
+    Generics {
+        params: vec![],
+        where_predicates: vec![],
+    }
+}
+
+fn function<'a>(
+    inputs: impl IntoIterator<Item = (&'a str, Type)>,
+    output: Option<Type>,
+    has_body: bool,
+) -> ItemEnum {
+    ItemEnum::Function(Function {
+        sig: FunctionSignature {
+            inputs: inputs
+                .into_iter()
+                .map(|(name, type_)| (name.to_string(), type_))
+                .collect(),
+            output,
+            is_c_variadic: false,
+        },
+        generics: empty_generics(),
+        header: FunctionHeader {
+            is_const: false,
+            is_unsafe: false,
+            is_async: false,
+            abi: Abi::Rust,
+        },
+        has_body,
+    })
+}
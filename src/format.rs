@@ -1,6 +1,6 @@
 use rustdoc_types::{
     FunctionSignature, GenericArg, GenericArgs, GenericBound, GenericParamDef, GenericParamDefKind,
-    Path, PolyTrait, Type,
+    Generics, Path, PolyTrait, PreciseCapturingArg, Term, Type, WherePredicate,
 };
 
 pub fn fn_sig_to_string(decl: &FunctionSignature) -> String {
@@ -63,7 +63,7 @@ pub fn generic_arg_to_string(arg: &GenericArg) -> String {
         GenericArg::Type(ty) => type_to_string(ty),
         GenericArg::Lifetime(lifetime) => lifetime.to_string(),
         GenericArg::Infer => "_".to_string(),
-        other => todo!("{:?}", other),
+        GenericArg::Const(constant) => constant.expr.clone(),
     }
 }
 
@@ -90,7 +90,18 @@ pub fn bound_to_string(bound: &GenericBound) -> String {
             s.push_str(&path_to_string(trait_));
             s
         }
-        other => todo!("{:?}", other),
+        GenericBound::Outlives(lifetime) => lifetime.clone(),
+        GenericBound::Use(args) => {
+            let args = args
+                .iter()
+                .map(|arg| match arg {
+                    PreciseCapturingArg::Lifetime(name) | PreciseCapturingArg::Param(name) => {
+                        name.as_str()
+                    }
+                })
+                .collect::<Vec<_>>();
+            format!("use<{}>", args.join(", "))
+        }
     }
 }
 
@@ -135,11 +146,60 @@ pub fn generic_param_def_to_string(param: &GenericParamDef) -> String {
                 s.push_str(&type_to_string(default));
             }
         }
-        other => todo!("{:?}", other),
+        GenericParamDefKind::Const { type_, default } => {
+            s.insert_str(0, "const ");
+            s.push_str(": ");
+            s.push_str(&type_to_string(type_));
+            if let Some(default) = default {
+                s.push_str(" = ");
+                s.push_str(default);
+            }
+        }
     }
     s
 }
 
+/// The generic parameters of `generics` with their bounds, e.g. `<'a, T: Clone>`, or an empty string.
+pub fn generics_to_string(generics: &Generics) -> String {
+    if generics.params.is_empty() {
+        return String::new();
+    }
+    let params = generics
+        .params
+        .iter()
+        .map(generic_param_def_to_string)
+        .collect::<Vec<_>>();
+    format!("<{}>", params.join(", "))
+}
+
+/// The where clause of `generics`, e.g. `where T: Send, 'a: 'b`, or an empty string.
+pub fn where_clause_to_string(generics: &Generics) -> String {
+    if generics.where_predicates.is_empty() {
+        return String::new();
+    }
+    let predicates = generics
+        .where_predicates
+        .iter()
+        .map(|predicate| match predicate {
+            WherePredicate::BoundPredicate { type_, bounds, .. } => {
+                let bounds = bounds.iter().map(bound_to_string).collect::<Vec<_>>();
+                format!("{}: {}", type_to_string(type_), bounds.join(" + "))
+            }
+            WherePredicate::LifetimePredicate { lifetime, outlives } => {
+                format!("{lifetime}: {}", outlives.join(" + "))
+            }
+            WherePredicate::EqPredicate { lhs, rhs } => {
+                let rhs = match rhs {
+                    Term::Type(type_) => type_to_string(type_),
+                    Term::Constant(constant) => constant.expr.clone(),
+                };
+                format!("{} = {rhs}", type_to_string(lhs))
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("where {}", predicates.join(", "))
+}
+
 pub fn type_to_string(ty: &Type) -> String {
     match ty {
         Type::Primitive(primitive) => primitive.to_string(),
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use rustdoc_types::{Id, Type};
+
+use crate::compat::NewerVersions;
+use crate::sysroot::SYSROOT_CRATES;
+use crate::workspace::is_library;
+use crate::{item_kind, AnyItem, BuildCrateError, Crate, CrateBuilder};
+
+/// A crate together with the rustdoc JSON of (some of) its dependencies.
+///
+/// Items of other crates appear in a crate's JSON only as [`rustdoc_types::ItemSummary`] entries in `paths`
+/// (or, for some traits, as incomplete entries of the index).
+/// `CrateGraph` maps such ids to the actual items of the loaded dependency crates.
+///
+/// The dependencies are attached to the root crate, so that [`Crate::resolve`], [`Crate::resolve_type`] and
+/// [`crate::UseItem::target`] of [`CrateGraph::root`] (or [`CrateGraph::into_root`]) resolve external ids transparently.
+///
+/// ```no_run
+/// use crate_inspector::graph::CrateGraph;
+/// use crate_inspector::{AnyItem, CrateBuilder};
+///
+/// let builder = CrateBuilder::default().toolchain("nightly").silent(true);
+/// let mut graph = CrateGraph::new(builder.clone().manifest_path("Cargo.toml").build().unwrap());
+/// graph.load_dependencies(builder, "Cargo.toml").unwrap();
+/// for func in graph.root().all_functions() {
+///     if let Some(AnyItem::Struct(strc)) = func.output().and_then(|ty| graph.root().resolve_type(ty)) {
+///         println!("{} returns {}", func.name(), strc.name());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrateGraph {
+    root: Crate,
+}
+
+impl CrateGraph {
+    /// Create a graph of `root` and the dependencies already attached to it, if any.
+    pub fn new(root: Crate) -> Self {
+        Self { root }
+    }
+
+    pub fn root(&self) -> &Crate {
+        &self.root
+    }
+
+    /// The root crate, with the loaded dependencies attached.
+    pub fn into_root(self) -> Crate {
+        self.root
+    }
+
+    /// Add a dependency crate. It replaces a previously added crate of the same name.
+    pub fn add_crate(&mut self, krate: Crate) {
+        Arc::make_mut(&mut self.root.dependencies).insert(krate.name().to_string(), krate);
+    }
+
+    /// Add a dependency crate from a rustdoc JSON file, e.g. one built earlier and cached.
+    pub fn load_json(&mut self, path: impl AsRef<Path>) -> Result<(), BuildCrateError> {
+        self.load_json_with(path, NewerVersions::default())
+    }
+
+    /// Like [`CrateGraph::load_json`], loading newer format versions according to `newer`.
+    pub fn load_json_with(
+        &mut self,
+        path: impl AsRef<Path>,
+        newer: NewerVersions,
+    ) -> Result<(), BuildCrateError> {
+        self.add_crate(Crate::from_path_with(path, newer)?);
+        Ok(())
+    }
+
+    /// Builds rustdoc JSON for each external crate referenced by the root crate which is a package in the dependency
+    /// graph of `manifest_path`, using `builder` as a template.
+    ///
+    /// Crates that are already loaded and crates that are not cargo packages (e.g. `std`) are skipped.
+    /// Build failures do not abort the loading; they are returned together with the crate name.
+    pub fn load_dependencies(
+        &mut self,
+        builder: CrateBuilder,
+        manifest_path: impl AsRef<Path>,
+    ) -> Result<Vec<(String, BuildCrateError)>, BuildCrateError> {
+        let manifest_path = manifest_path.as_ref();
+        let metadata = builder
+            .metadata_command()
+            .manifest_path(manifest_path)
+            .exec()?;
+        let mut names = self
+            .root
+            .external_crates
+            .values()
+            .map(|external| external.name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        let mut errors = vec![];
+        for name in names {
+            if self.root.dependencies.contains_key(&name) {
+                continue;
+            }
+            let Some(package) = metadata.packages.iter().find(|package| {
+                package
+                    .targets
+                    .iter()
+                    .any(|target| is_library(target) && target.name.replace('-', "_") == name)
+            }) else {
+                continue;
+            };
+            let result = builder
+                .clone()
+                .manifest_path(manifest_path)
+                .package(&package.name)
+                .build();
+            match result {
+                Ok(krate) => self.add_crate(krate),
+                Err(err) => errors.push((name, err)),
+            }
+        }
+        Ok(errors)
+    }
+
+    /// Loads the standard library crates (`std`, `core`, `alloc`, ...) referenced by the root crate
+    /// from the `rust-docs-json` component of `toolchain` (or the active toolchain if `None`).
+    /// See [`Crate::load_sysroot_for`].
+    pub fn load_sysroot(&mut self, toolchain: Option<&str>) -> Result<(), BuildCrateError> {
+        self.load_sysroot_with(toolchain, NewerVersions::default())
+    }
+
+    /// Like [`CrateGraph::load_sysroot`], loading newer format versions according to `newer`.
+    pub fn load_sysroot_with(
+        &mut self,
+        toolchain: Option<&str>,
+        newer: NewerVersions,
+    ) -> Result<(), BuildCrateError> {
+        let names = self
+            .root
+            .external_crates
+            .values()
+            .filter(|external| SYSROOT_CRATES.contains(&external.name.as_str()))
+            .map(|external| external.name.clone())
+            .collect::<Vec<_>>();
+        for name in names {
+            if self.root.dependencies.contains_key(&name) {
+                continue;
+            }
+            self.add_crate(Crate::load_sysroot_with(toolchain, &name, newer)?);
+        }
+        Ok(())
+    }
+
+    /// Get a loaded dependency crate by its crate name (e.g. `serde_json`, not `serde-json`).
+    pub fn get_crate(&self, name: &str) -> Option<&Crate> {
+        if self.root.name() == name {
+            Some(&self.root)
+        } else {
+            self.root.dependencies.get(name)
+        }
+    }
+
+    /// The root crate followed by the loaded dependencies, ordered by name.
+    pub fn crates(&self) -> impl Iterator<Item = &Crate> {
+        std::iter::once(&self.root).chain(self.root.dependencies.values())
+    }
+
+    /// Resolve an id of the root crate. See [`CrateGraph::resolve`].
+    pub fn get(&self, id: &Id) -> Option<AnyItem<'_>> {
+        self.resolve(&self.root, id)
+    }
+
+    /// Resolve `id`, an id used in `from` (the root or one of the loaded crates).
+    ///
+    /// Local items are returned as is. Items of other crates are looked up in the loaded crates by their path;
+    /// if the crate is not loaded, the (possibly incomplete) entry of `from`'s index is returned, if any.
+    pub fn resolve<'a>(&'a self, from: &'a Crate, id: &Id) -> Option<AnyItem<'a>> {
+        resolve(&self.root.dependencies, from, id)
+    }
+
+    /// Resolve the item a path (e.g. of a [`Type::ResolvedPath`] or a trait bound) refers to.
+    pub fn resolve_path<'a>(
+        &'a self,
+        from: &'a Crate,
+        path: &rustdoc_types::Path,
+    ) -> Option<AnyItem<'a>> {
+        self.resolve(from, &path.id)
+    }
+
+    /// Resolve the item a type refers to, looking through references, raw pointers, slices and arrays.
+    pub fn resolve_type<'a>(&'a self, from: &'a Crate, ty: &Type) -> Option<AnyItem<'a>> {
+        resolve_type(ty, &|id| self.resolve(from, id))
+    }
+}
+
+impl Crate {
+    /// Get the item `id`, following ids of external items into the dependencies attached by [`CrateGraph`].
+    /// See [`CrateGraph::resolve`].
+    pub fn resolve(&self, id: &Id) -> Option<AnyItem<'_>> {
+        resolve(&self.dependencies, self, id)
+    }
+
+    /// Resolve the item a type refers to, looking through references, raw pointers, slices and arrays.
+    /// See [`Crate::resolve`].
+    pub fn resolve_type(&self, ty: &Type) -> Option<AnyItem<'_>> {
+        resolve_type(ty, &|id| self.resolve(id))
+    }
+}
+
+fn resolve<'a>(
+    dependencies: &'a BTreeMap<String, Crate>,
+    from: &'a Crate,
+    id: &Id,
+) -> Option<AnyItem<'a>> {
+    let local = from.index.get(id);
+    if let Some(item) = local.filter(|item| item.crate_id == 0) {
+        return Some(from.any_item(item));
+    }
+    resolve_external(dependencies, from, id).or_else(|| local.map(|item| from.any_item(item)))
+}
+
+fn resolve_external<'a>(
+    dependencies: &'a BTreeMap<String, Crate>,
+    from: &'a Crate,
+    id: &Id,
+) -> Option<AnyItem<'a>> {
+    let summary = from.paths.get(id)?;
+    let name = &from.external_crates.get(&summary.crate_id)?.name;
+    if let Some(krate) = dependencies.get(name) {
+        let candidates = krate
+            .ids_by_path(&summary.path.join("::"))
+            .iter()
+            .filter_map(|id| krate.index.get(id))
+            .collect::<Vec<_>>();
+        let item = candidates
+            .iter()
+            .find(|item| item_kind(item) == summary.kind)
+            .or(candidates.first());
+        if let Some(item) = item {
+            return Some(krate.any_item(item));
+        }
+    }
+    // The defining crate is not loaded (or does not list the item): look for the item in the `paths` of the other
+    // loaded crates, e.g. a crate re-exporting it whose index contains it
+    dependencies.values().find_map(|krate| {
+        let item = krate
+            .paths
+            .iter()
+            .filter(|(_, other)| other.kind == summary.kind && other.path == summary.path)
+            .filter_map(|(id, _)| krate.index.get(id))
+            .min_by_key(|item| item.id)?;
+        Some(krate.any_item(item))
+    })
+}
+
+fn resolve_type<'a>(
+    ty: &Type,
+    resolve: &impl Fn(&Id) -> Option<AnyItem<'a>>,
+) -> Option<AnyItem<'a>> {
+    match ty {
+        Type::ResolvedPath(path) => resolve(&path.id),
+        Type::BorrowedRef { type_, .. }
+        | Type::RawPointer { type_, .. }
+        | Type::Slice(type_)
+        | Type::Array { type_, .. } => resolve_type(type_, resolve),
+        _ => None,
+    }
+}
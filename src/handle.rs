@@ -0,0 +1,81 @@
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use rustdoc_types::Id;
+
+use crate::{AnyItem, Crate, CrateItem};
+
+/// An owned, lifetime-free reference to an item of a shared [`Crate`].
+///
+/// Unlike the borrowed wrappers such as [`StructItem`](crate::StructItem), handles can be stored in long-lived structs,
+/// cached or sent to other threads. Upgrade them to the borrowed wrappers with [`ItemHandle::get`] or [`ItemHandle::downcast`].
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use crate_inspector::{CrateBuilder, CrateItem, StructItem};
+///
+/// let krate = Arc::new(CrateBuilder::default().toolchain("nightly").build().unwrap());
+/// let handles = krate
+///     .all_structs()
+///     .map(|strc| krate.handle(strc.item().id).unwrap())
+///     .collect::<Vec<_>>();
+/// std::thread::spawn(move || {
+///     for handle in handles {
+///         let strc = handle.downcast::<StructItem>().unwrap();
+///         println!("struct: {}", strc.name());
+///     }
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ItemHandle {
+    krate: Arc<Crate>,
+    id: Id,
+}
+
+impl PartialEq for ItemHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.krate, &other.krate) && self.id == other.id
+    }
+}
+
+impl Eq for ItemHandle {}
+
+impl Hash for ItemHandle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.krate).hash(state);
+        self.id.hash(state);
+    }
+}
+
+impl ItemHandle {
+    /// Returns `None` if `id` is not in the index of `krate`.
+    pub fn new(krate: Arc<Crate>, id: Id) -> Option<Self> {
+        if krate.index.contains_key(&id) {
+            Some(Self { krate, id })
+        } else {
+            None
+        }
+    }
+
+    pub fn krate(&self) -> &Arc<Crate> {
+        &self.krate
+    }
+
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+
+    pub fn item(&self) -> &rustdoc_types::Item {
+        &self.krate.index[&self.id]
+    }
+
+    /// Upgrade to the borrowed wrapper of the item.
+    pub fn get(&self) -> AnyItem<'_> {
+        self.krate.any_item(self.item())
+    }
+
+    /// Upgrade to a specific wrapper type `T: CrateItem`.
+    pub fn downcast<'a, T: CrateItem<'a> + 'a>(&'a self) -> Option<T> {
+        self.krate.downcast::<T>(self.item())
+    }
+}
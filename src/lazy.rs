@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use rustdoc_types::{ExternalCrate, Id, Item, ItemSummary, Target};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use crate::compat::{self, NewerVersions, FORMAT_VERSION};
+use crate::{read_file, BuildCrateError, Crate};
+
+/// A crate whose items are parsed on first access.
+///
+/// Loading only splits the `index` of the rustdoc JSON into the unparsed JSON of each item, which is much faster and
+/// takes less memory than parsing every item for huge crates (e.g. `windows-sys`). Queries parse (and cache) only the items they touch;
+/// [`LazyCrate::into_crate`] parses the rest to get a [`Crate`].
+///
+/// ```no_run
+/// use crate_inspector::CrateBuilder;
+///
+/// let krate = CrateBuilder::default()
+///     .toolchain("nightly")
+///     .manifest_path("windows-sys/Cargo.toml")
+///     .build_lazy()
+///     .unwrap();
+/// let item = krate.get_by_path("windows_sys::Win32::Foundation::CloseHandle").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct LazyCrate {
+    root: Id,
+    crate_version: Option<String>,
+    includes_private: bool,
+    index: HashMap<Id, LazyItem>,
+    paths: LazyPaths,
+    external_crates: HashMap<u32, ExternalCrate>,
+    target: Target,
+    format_version: u32,
+}
+
+#[derive(Debug)]
+struct LazyItem {
+    raw: Option<Box<RawValue>>,
+    item: OnceLock<Item>,
+}
+
+#[derive(Debug)]
+struct LazyPaths {
+    raw: Option<Box<RawValue>>,
+    paths: OnceLock<HashMap<Id, ItemSummary>>,
+    /// The local items by canonical path, built on the first [`LazyCrate::get_by_path`].
+    by_path: OnceLock<HashMap<String, Id>>,
+}
+
+/// rustdoc JSON, with the items and paths left unparsed.
+#[derive(Deserialize)]
+struct RawCrate {
+    root: Id,
+    crate_version: Option<String>,
+    includes_private: bool,
+    index: HashMap<Id, Box<RawValue>>,
+    paths: Box<RawValue>,
+    external_crates: HashMap<u32, ExternalCrate>,
+    target: Target,
+    format_version: u32,
+}
+
+impl LazyCrate {
+    /// Load a crate from a rustdoc JSON file. See [`LazyCrate::from_slice`].
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, BuildCrateError> {
+        Self::from_path_with(path, NewerVersions::default())
+    }
+
+    /// Like [`LazyCrate::from_path`], loading newer format versions according to `newer`.
+    pub fn from_path_with(
+        path: impl AsRef<Path>,
+        newer: NewerVersions,
+    ) -> Result<Self, BuildCrateError> {
+        Self::from_slice_with(&read_file(path.as_ref())?, newer)
+    }
+
+    /// Load a crate from rustdoc JSON, without parsing its items.
+    ///
+    /// JSON of an older format version is upgraded (see [`compat`]), which requires parsing it completely.
+    /// Newer format versions are handled as in [`Crate::from_slice`]; their items may still fail to parse later.
+    pub fn from_slice(json: &[u8]) -> Result<Self, BuildCrateError> {
+        Self::from_slice_with(json, NewerVersions::default())
+    }
+
+    /// Like [`LazyCrate::from_slice`], loading newer format versions according to `newer`.
+    pub fn from_slice_with(json: &[u8], newer: NewerVersions) -> Result<Self, BuildCrateError> {
+        let version = compat::format_version(json)?;
+        if version != FORMAT_VERSION && compat::is_supported(version) {
+            return Ok(Self::from(compat::deserialize(json, newer)?));
+        }
+        compat::check_newer(version, newer)?;
+        let raw = serde_json::from_slice::<RawCrate>(json).map_err(|err| {
+            if version == FORMAT_VERSION {
+                err.into()
+            } else {
+                compat::unsupported(version, Some(err))
+            }
+        })?;
+        Ok(Self {
+            root: raw.root,
+            crate_version: raw.crate_version,
+            includes_private: raw.includes_private,
+            index: raw
+                .index
+                .into_iter()
+                .map(|(id, raw)| {
+                    let item = LazyItem {
+                        raw: Some(raw),
+                        item: OnceLock::new(),
+                    };
+                    (id, item)
+                })
+                .collect(),
+            paths: LazyPaths {
+                raw: Some(raw.paths),
+                paths: OnceLock::new(),
+                by_path: OnceLock::new(),
+            },
+            external_crates: raw.external_crates,
+            target: raw.target,
+            format_version: raw.format_version,
+        })
+    }
+
+    pub fn root(&self) -> &Id {
+        &self.root
+    }
+
+    /// The name of the crate, i.e. the name of the root module.
+    pub fn name(&self) -> Result<&str, BuildCrateError> {
+        Ok(self
+            .get(&self.root)?
+            .and_then(|root| root.name.as_deref())
+            .unwrap_or_default())
+    }
+
+    pub fn crate_version(&self) -> Option<&str> {
+        self.crate_version.as_deref()
+    }
+
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    pub fn external_crates(&self) -> &HashMap<u32, ExternalCrate> {
+        &self.external_crates
+    }
+
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// The ids of all items in the index, without parsing them.
+    pub fn ids(&self) -> impl Iterator<Item = &Id> {
+        self.index.keys()
+    }
+
+    /// The number of items in the index.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Get an item, parsing it on first access.
+    pub fn get(&self, id: &Id) -> Result<Option<&Item>, BuildCrateError> {
+        let Some(lazy) = self.index.get(id) else {
+            return Ok(None);
+        };
+        if let Some(item) = lazy.item.get() {
+            return Ok(Some(item));
+        }
+        let raw = lazy.raw.as_ref().expect("an unparsed item has its JSON");
+        let item = compat::deserialize_item(raw.get())?;
+        Ok(Some(lazy.item.get_or_init(|| item)))
+    }
+
+    /// The paths of the items (the `paths` of the rustdoc JSON), parsed on first access.
+    pub fn paths(&self) -> Result<&HashMap<Id, ItemSummary>, BuildCrateError> {
+        if let Some(paths) = self.paths.paths.get() {
+            return Ok(paths);
+        }
+        let raw = self
+            .paths
+            .raw
+            .as_ref()
+            .expect("unparsed paths have their JSON");
+        let paths = serde_json::from_str(raw.get())?;
+        Ok(self.paths.paths.get_or_init(|| paths))
+    }
+
+    /// Get a local item by its canonical path, e.g. `"serde::ser::Serialize"`.
+    ///
+    /// The index of paths is built on first use. If items of different kinds share the path, the first one is returned.
+    pub fn get_by_path(&self, path: &str) -> Result<Option<&Item>, BuildCrateError> {
+        let paths = self.paths()?;
+        let index = self.paths.by_path.get_or_init(|| {
+            let mut index = HashMap::<String, Id>::new();
+            for (id, summary) in paths {
+                if summary.crate_id == 0 {
+                    index
+                        .entry(summary.path.join("::"))
+                        .and_modify(|first| *first = (*first).min(*id))
+                        .or_insert(*id);
+                }
+            }
+            index
+        });
+        match index.get(path) {
+            Some(id) => self.get(id),
+            None => Ok(None),
+        }
+    }
+
+    /// Parse all remaining items.
+    pub fn into_crate(self) -> Result<Crate, BuildCrateError> {
+        let paths = match self.paths.paths.into_inner() {
+            Some(paths) => paths,
+            None => serde_json::from_str(self.paths.raw.as_ref().unwrap().get())?,
+        };
+        let index = self
+            .index
+            .into_iter()
+            .map(|(id, lazy)| {
+                let item = match lazy.item.into_inner() {
+                    Some(item) => item,
+                    None => compat::deserialize_item(lazy.raw.as_ref().unwrap().get())?,
+                };
+                Ok((id, item))
+            })
+            .collect::<Result<_, BuildCrateError>>()?;
+        Ok(Crate::new(rustdoc_types::Crate {
+            root: self.root,
+            crate_version: self.crate_version,
+            includes_private: self.includes_private,
+            index,
+            paths,
+            external_crates: self.external_crates,
+            target: self.target,
+            format_version: self.format_version,
+        }))
+    }
+}
+
+impl From<rustdoc_types::Crate> for LazyCrate {
+    /// Wrap a parsed crate; its items are available without parsing.
+    fn from(krate: rustdoc_types::Crate) -> Self {
+        Self {
+            root: krate.root,
+            crate_version: krate.crate_version,
+            includes_private: krate.includes_private,
+            index: krate
+                .index
+                .into_iter()
+                .map(|(id, item)| {
+                    let item = LazyItem {
+                        raw: None,
+                        item: OnceLock::from(item),
+                    };
+                    (id, item)
+                })
+                .collect(),
+            paths: LazyPaths {
+                raw: None,
+                paths: OnceLock::from(krate.paths),
+                by_path: OnceLock::new(),
+            },
+            external_crates: krate.external_crates,
+            target: krate.target,
+            format_version: krate.format_version,
+        }
+    }
+}
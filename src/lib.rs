@@ -1,10 +1,40 @@
+pub mod api;
+mod build;
+mod cache;
+pub mod compat;
+pub mod features;
+pub mod fingerprint;
+pub mod fixture;
 pub mod format;
-
+pub mod graph;
+pub mod handle;
+pub mod lazy;
+#[cfg(feature = "rayon")]
+mod par;
+pub mod progress;
+pub mod prune;
+mod scaffold;
+pub mod snapshot;
+pub mod stable_id;
+mod sysroot;
+pub mod targets;
+mod util;
+mod visibility;
+pub mod walk;
+#[cfg(feature = "watch")]
+pub mod watch;
+pub mod workspace;
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
 use std::io::Write;
 use std::ops::Deref;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
-use rustdoc_json::Color;
+use cargo_metadata::diagnostic::{Diagnostic, DiagnosticLevel};
 use rustdoc_types::{Id, Type};
 
 pub trait CrateItem<'a> {
@@ -30,11 +60,88 @@ pub trait CrateItem<'a> {
     fn id(&'a self) -> &'a Id {
         &self.item().id
     }
+    /// The module listing the item among its items, or `None` for e.g. fields and methods.
+    ///
+    /// If several modules list the item (e.g. inlined re-exports), this is the module of its canonical path,
+    /// falling back to the module whose source contains it, then to the module with the lowest id.
     fn module(&self) -> Option<ModuleItem<'a>> {
-        self.krate()
-            .all_modules()
-            .find(|module| module.module.items.contains(&self.item().id))
+        let krate = self.krate();
+        krate
+            .parent_id(&self.item().id)
+            .and_then(|id| krate.downcast::<ModuleItem>(&krate.index[id]))
+    }
+    fn visibility(&self) -> &'a rustdoc_types::Visibility {
+        &self.item().visibility
     }
+    /// The module outside of which the item is not visible, e.g. the crate root for `pub(crate)` items,
+    /// `foo` for `pub(in crate::foo)` items and the defining module for private items.
+    /// `None` if the item is visible everywhere.
+    ///
+    /// Enum variants and trait items inherit the scope of their parent, and so do the fields of variants.
+    fn visibility_scope(&self) -> Option<ModuleItem<'a>> {
+        let krate = self.krate();
+        visibility::scope(krate, self.item())
+            .and_then(|id| krate.index.get(id))
+            .and_then(|item| krate.downcast::<ModuleItem>(item))
+    }
+    /// Whether the item's visibility allows it to be used from `module`.
+    ///
+    /// This only evaluates the visibility of the item itself, not whether the modules on the way to it are visible.
+    fn is_visible_from(&self, module: &ModuleItem<'_>) -> bool {
+        let krate = self.krate();
+        match visibility::scope(krate, self.item()) {
+            Some(scope) => visibility::is_within(krate, module.id(), scope),
+            None => true,
+        }
+    }
+    /// Whether the item can be named by downstream crates.
+    /// Unlike [`CrateItem::is_public`], this accounts for private parent modules, re-exports and `#[doc(hidden)]`.
+    /// See [`Crate::public_api`].
+    fn is_reachable(&self) -> bool {
+        self.krate().public_api().contains(&self.item().id)
+    }
+    /// Whether the item is marked `#[doc(hidden)]`.
+    /// Such items are only present when the crate is built with `--document-hidden-items`.
+    fn is_doc_hidden(&self) -> bool {
+        is_doc_hidden(self.item())
+    }
+}
+
+pub(crate) fn item_kind(item: &rustdoc_types::Item) -> rustdoc_types::ItemKind {
+    use rustdoc_types::{ItemEnum, ItemKind, MacroKind};
+    match &item.inner {
+        ItemEnum::Module(_) => ItemKind::Module,
+        ItemEnum::ExternCrate { .. } => ItemKind::ExternCrate,
+        ItemEnum::Use(_) => ItemKind::Use,
+        ItemEnum::Union(_) => ItemKind::Union,
+        ItemEnum::Struct(_) => ItemKind::Struct,
+        ItemEnum::StructField(_) => ItemKind::StructField,
+        ItemEnum::Enum(_) => ItemKind::Enum,
+        ItemEnum::Variant(_) => ItemKind::Variant,
+        ItemEnum::Function(_) => ItemKind::Function,
+        ItemEnum::Trait(_) => ItemKind::Trait,
+        ItemEnum::TraitAlias(_) => ItemKind::TraitAlias,
+        ItemEnum::Impl(_) => ItemKind::Impl,
+        ItemEnum::TypeAlias(_) => ItemKind::TypeAlias,
+        ItemEnum::Constant { .. } => ItemKind::Constant,
+        ItemEnum::Static(_) => ItemKind::Static,
+        ItemEnum::ExternType => ItemKind::ExternType,
+        ItemEnum::Macro(_) => ItemKind::Macro,
+        ItemEnum::ProcMacro(proc_macro) => match proc_macro.kind {
+            MacroKind::Bang => ItemKind::Macro,
+            MacroKind::Attr => ItemKind::ProcAttribute,
+            MacroKind::Derive => ItemKind::ProcDerive,
+        },
+        ItemEnum::Primitive(_) => ItemKind::Primitive,
+        ItemEnum::AssocConst { .. } => ItemKind::AssocConst,
+        ItemEnum::AssocType { .. } => ItemKind::AssocType,
+    }
+}
+
+pub(crate) fn is_doc_hidden(item: &rustdoc_types::Item) -> bool {
+    item.attrs.iter().any(|attr| {
+        matches!(attr, rustdoc_types::Attribute::Other(attr) if attr.replace(' ', "") == "#[doc(hidden)]")
+    })
 }
 
 pub trait HasType {
@@ -212,9 +319,7 @@ impl<'a> ModuleItem<'a> {
     }
 
     pub fn parent(&self) -> Option<ModuleItem<'a>> {
-        self.krate
-            .all_modules()
-            .find(|module| module.module.items.contains(&self.item.id))
+        self.module()
     }
 
     pub fn is_crate(&self) -> bool {
@@ -224,6 +329,11 @@ impl<'a> ModuleItem<'a> {
     pub fn is_stripped(&self) -> bool {
         self.module.is_stripped
     }
+
+    /// Whether `self` is `other` or one of its descendants.
+    pub fn is_within(&self, other: &ModuleItem<'_>) -> bool {
+        visibility::is_within(self.krate, &self.item.id, &other.item.id)
+    }
 }
 
 impl_items!(ModuleItem<'a>);
@@ -277,15 +387,13 @@ impl<'a> FunctionItem<'a> {
     }
 
     pub fn is_associated(&self) -> bool {
-        self.krate
-            .all_impls()
-            .any(|imp| imp.item_ids().any(|id| id == &self.item.id))
+        self.associated_impl().is_some()
     }
 
     pub fn associated_impl(&self) -> Option<ImplItem<'a>> {
         self.krate
-            .all_impls()
-            .find(|imp| imp.item_ids().any(|id| id == &self.item.id))
+            .parent_id(&self.item.id)
+            .and_then(|id| self.krate.downcast::<ImplItem>(&self.krate.index[id]))
     }
 
     pub fn inputs(&self) -> impl Iterator<Item = &(String, Type)> {
@@ -1200,6 +1308,7 @@ impl<'a> MacroItem<'a> {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct UseItem<'a> {
     krate: &'a Crate,
     item: &'a rustdoc_types::Item,
@@ -1232,7 +1341,7 @@ impl<'a> CrateItem<'a> for UseItem<'a> {
     }
 }
 
-impl UseItem<'_> {
+impl<'a> UseItem<'a> {
     /// e.g.
     ///
     /// `pub use foo::bar;` -> as_name == "bar", source == "foo::bar", is_glob == false
@@ -1258,10 +1367,135 @@ impl UseItem<'_> {
     pub fn is_glob(&self) -> bool {
         self.import.is_glob
     }
+
+    /// The imported item, if it is present in the index or in a dependency attached by [`graph::CrateGraph`].
+    pub fn target(&self) -> Option<&'a rustdoc_types::Item> {
+        self.import
+            .id
+            .as_ref()
+            .and_then(|id| self.krate.resolve(id))
+            .map(|item| item.item())
+    }
+}
+
+/// Any item of the crate, downcast to its wrapper type where one exists.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnyItem<'a> {
+    Module(ModuleItem<'a>),
+    Function(FunctionItem<'a>),
+    Constant(ConstantItem<'a>),
+    Static(StaticItem<'a>),
+    Struct(StructItem<'a>),
+    Field(FieldItem<'a>),
+    Trait(TraitItem<'a>),
+    Enum(EnumItem<'a>),
+    Variant(VariantItem<'a>),
+    Union(UnionItem<'a>),
+    TypeAlias(TypeAliasItem<'a>),
+    TraitAlias(TraitAliasItem<'a>),
+    Impl(ImplItem<'a>),
+    Macro(MacroItem<'a>),
+    Use(UseItem<'a>),
+    /// Items without a dedicated wrapper (extern crates, primitives, associated items, ...).
+    Other(&'a rustdoc_types::Item),
+}
+
+impl<'a> AnyItem<'a> {
+    pub fn new(krate: &'a Crate, item: &'a rustdoc_types::Item) -> Self {
+        use rustdoc_types::ItemEnum;
+        match &item.inner {
+            ItemEnum::Module(module) => Self::Module(ModuleItem::new(krate, item, module)),
+            ItemEnum::Function(func) => Self::Function(FunctionItem::new(krate, item, func)),
+            ItemEnum::Constant { .. } => {
+                Self::Constant(ConstantItem::new(krate, item, &item.inner))
+            }
+            ItemEnum::Static(static_) => Self::Static(StaticItem::new(krate, item, static_)),
+            ItemEnum::Struct(struct_) => Self::Struct(StructItem::new(krate, item, struct_)),
+            ItemEnum::StructField(field) => Self::Field(FieldItem::new(krate, item, field)),
+            ItemEnum::Trait(trait_) => Self::Trait(TraitItem::new(krate, item, trait_)),
+            ItemEnum::Enum(enum_) => Self::Enum(EnumItem::new(krate, item, enum_)),
+            ItemEnum::Variant(variant) => Self::Variant(VariantItem::new(krate, item, variant)),
+            ItemEnum::Union(union) => Self::Union(UnionItem::new(krate, item, union)),
+            ItemEnum::TypeAlias(type_alias) => {
+                Self::TypeAlias(TypeAliasItem::new(krate, item, type_alias))
+            }
+            ItemEnum::TraitAlias(trait_alias) => {
+                Self::TraitAlias(TraitAliasItem::new(krate, item, trait_alias))
+            }
+            ItemEnum::Impl(impl_) => Self::Impl(ImplItem::new(krate, item, impl_)),
+            ItemEnum::Macro(macro_) => Self::Macro(MacroItem::new(krate, item, macro_)),
+            ItemEnum::Use(import) => Self::Use(UseItem::new(krate, item, import)),
+            _ => Self::Other(item),
+        }
+    }
+
+    pub fn item(&self) -> &'a rustdoc_types::Item {
+        match self {
+            Self::Module(module) => module.item(),
+            Self::Function(func) => func.item(),
+            Self::Constant(constant) => constant.item(),
+            Self::Static(static_) => static_.item(),
+            Self::Struct(struct_) => struct_.item(),
+            Self::Field(field) => field.item(),
+            Self::Trait(trait_) => trait_.item(),
+            Self::Enum(enum_) => enum_.item(),
+            Self::Variant(variant) => variant.item(),
+            Self::Union(union) => union.item(),
+            Self::TypeAlias(type_alias) => type_alias.item(),
+            Self::TraitAlias(trait_alias) => trait_alias.item(),
+            Self::Impl(impl_) => impl_.item(),
+            Self::Macro(macro_) => macro_.item(),
+            Self::Use(import) => import.item(),
+            Self::Other(item) => item,
+        }
+    }
+
+    pub fn id(&self) -> &'a Id {
+        &self.item().id
+    }
+
+    pub fn kind(&self) -> rustdoc_types::ItemKind {
+        item_kind(self.item())
+    }
+
+    /// `None` for impls and other unnamed items.
+    pub fn name(&self) -> Option<&'a str> {
+        match self {
+            Self::Use(import) => Some(&import.import.name),
+            _ => self.item().name.as_deref(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Crate {
+    krate: rustdoc_types::Crate,
+    public_api: OnceLock<api::PublicApi>,
+    parents: OnceLock<HashMap<Id, Id>>,
+    paths_index: OnceLock<HashMap<String, Vec<Id>>>,
+    stable_ids: OnceLock<HashMap<stable_id::StableId, Id>>,
+    dependencies: Arc<BTreeMap<String, Crate>>,
+}
+
+// `Crate` is meant to be shared across threads, e.g. through `ItemHandle`.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Crate>();
+};
+
+impl PartialEq for Crate {
+    fn eq(&self, other: &Self) -> bool {
+        self.krate == other.krate
+    }
 }
 
-#[derive(Clone, PartialEq, Eq)]
-pub struct Crate(rustdoc_types::Crate);
+impl Eq for Crate {}
+
+impl From<rustdoc_types::Crate> for Crate {
+    fn from(krate: rustdoc_types::Crate) -> Self {
+        Self::new(krate)
+    }
+}
 
 impl std::fmt::Debug for Crate {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -1277,14 +1511,81 @@ impl Deref for Crate {
     type Target = rustdoc_types::Crate;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.krate
     }
 }
 
 impl Crate {
+    pub fn new(krate: rustdoc_types::Crate) -> Self {
+        Self {
+            krate,
+            public_api: OnceLock::new(),
+            parents: OnceLock::new(),
+            paths_index: OnceLock::new(),
+            stable_ids: OnceLock::new(),
+            dependencies: Arc::default(),
+        }
+    }
+
+    /// Load a crate from a rustdoc JSON file. See [`Crate::from_slice`].
+    ///
+    /// With the `mmap` feature, the file is memory-mapped instead of being read into memory first.
+    /// For huge crates, see also [`lazy::LazyCrate`].
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, BuildCrateError> {
+        Self::from_path_with(path, compat::NewerVersions::default())
+    }
+
+    /// Like [`Crate::from_path`], loading newer format versions according to `newer`.
+    pub fn from_path_with(
+        path: impl AsRef<Path>,
+        newer: compat::NewerVersions,
+    ) -> Result<Self, BuildCrateError> {
+        Self::from_slice_with(&read_file(path.as_ref())?, newer)
+    }
+
+    /// Load a crate from a reader of rustdoc JSON. See [`Crate::from_slice`].
+    ///
+    /// The JSON is read into memory first, which is much faster than parsing it from the reader directly.
+    pub fn from_reader(reader: impl std::io::Read) -> Result<Self, BuildCrateError> {
+        Self::from_reader_with(reader, compat::NewerVersions::default())
+    }
+
+    /// Like [`Crate::from_reader`], loading newer format versions according to `newer`.
+    pub fn from_reader_with(
+        mut reader: impl std::io::Read,
+        newer: compat::NewerVersions,
+    ) -> Result<Self, BuildCrateError> {
+        let mut json = vec![];
+        reader.read_to_end(&mut json)?;
+        Self::from_slice_with(&json, newer)
+    }
+
+    /// Load a crate from rustdoc JSON.
+    ///
+    /// JSON of an older format version supported by [`compat`] is upgraded to [`compat::FORMAT_VERSION`] first.
+    /// Newer versions and versions older than [`compat::MIN_FORMAT_VERSION`] are loaded if they happen to
+    /// deserialize, and fail with [`BuildCrateError::FormatVersion`] otherwise. To reject newer versions outright,
+    /// see [`Crate::from_slice_with`].
+    pub fn from_slice(json: &[u8]) -> Result<Self, BuildCrateError> {
+        Self::from_slice_with(json, compat::NewerVersions::default())
+    }
+
+    /// Like [`Crate::from_slice`], loading newer format versions according to `newer`.
+    pub fn from_slice_with(
+        json: &[u8],
+        newer: compat::NewerVersions,
+    ) -> Result<Self, BuildCrateError> {
+        compat::deserialize(json, newer).map(Crate::new)
+    }
+
+    /// The name of the crate, i.e. the name of the root module.
+    pub fn name(&self) -> &str {
+        self.index[&self.root].name.as_deref().unwrap_or_default()
+    }
+
     /// All items in the crate, including external items referenced locally.
     pub fn all_items(&self) -> impl Iterator<Item = &rustdoc_types::Item> {
-        self.0.index.values()
+        self.krate.index.values()
     }
 
     /// Items in the crate, excluding external items referenced locally.
@@ -1297,7 +1598,70 @@ impl Crate {
     }
 
     pub fn item_summary(&self) -> impl Iterator<Item = &rustdoc_types::ItemSummary> {
-        self.0.paths.values()
+        self.krate.paths.values()
+    }
+
+    /// Get a local item by its canonical path, e.g. `"serde::ser::Serialize"`.
+    pub fn get_item_by_path(&self, path: &str) -> Option<&rustdoc_types::Item> {
+        self.ids_by_path(path)
+            .iter()
+            .find_map(|id| self.index.get(id))
+    }
+
+    /// Ids of the local items with the canonical path `path`.
+    /// Items of different namespaces (e.g. a function and a macro) may share a path.
+    pub(crate) fn ids_by_path(&self, path: &str) -> &[Id] {
+        let index = self.paths_index.get_or_init(|| {
+            let mut index = HashMap::<String, Vec<Id>>::new();
+            for (id, summary) in self.paths.iter() {
+                if summary.crate_id == 0 {
+                    index.entry(summary.path.join("::")).or_default().push(*id);
+                }
+            }
+            index
+        });
+        index.get(path).map_or(&[], |ids| ids.as_slice())
+    }
+
+    /// The root module of the crate.
+    pub fn root_module(&self) -> ModuleItem<'_> {
+        self.downcast::<ModuleItem>(&self.index[&self.root])
+            .expect("the crate root must be a module")
+    }
+
+    /// The set of items reachable by downstream crates. Computed on first use.
+    pub fn public_api(&self) -> &api::PublicApi {
+        self.public_api.get_or_init(|| api::PublicApi::new(self))
+    }
+
+    /// The id of the module, type, trait or impl directly containing `id`.
+    pub(crate) fn parent_id(&self, id: &Id) -> Option<&Id> {
+        self.parents
+            .get_or_init(|| visibility::parents(self))
+            .get(id)
+    }
+
+    /// Items reachable by downstream crates. See [`Crate::public_api`].
+    pub fn reachable_items(&self) -> impl Iterator<Item = &rustdoc_types::Item> {
+        let public_api = self.public_api();
+        self.all_items()
+            .filter(move |item| public_api.contains(&item.id))
+    }
+
+    /// Create an owned handle to the item `id`. Returns `None` if `id` is not in the index.
+    pub fn handle(self: &Arc<Self>, id: Id) -> Option<handle::ItemHandle> {
+        handle::ItemHandle::new(Arc::clone(self), id)
+    }
+
+    /// Wrap an item into [`AnyItem`].
+    pub fn any_item<'a>(&'a self, item: &'a rustdoc_types::Item) -> AnyItem<'a> {
+        AnyItem::new(self, item)
+    }
+
+    /// Walks the module tree depth-first from the root, yielding each item with its fully qualified path.
+    /// See [`walk::Walk`] for the available options.
+    pub fn walk(&self) -> walk::Walk<'_> {
+        walk::Walk::new(self)
     }
 
     /// Downcast an item to a specific type `T: CrateItem`.
@@ -1517,28 +1881,164 @@ impl Crate {
     }
 }
 
+/// Reads a rustdoc JSON file.
+#[cfg(not(feature = "mmap"))]
+pub(crate) fn read_file(path: &Path) -> Result<Vec<u8>, BuildCrateError> {
+    Ok(std::fs::read(path)?)
+}
+
+/// Memory-maps a rustdoc JSON file.
+#[cfg(feature = "mmap")]
+pub(crate) fn read_file(path: &Path) -> Result<memmap2::Mmap, BuildCrateError> {
+    let file = std::fs::File::open(path)?;
+    // SAFETY: the map is only read while the JSON is parsed. Like any memory-mapped file, it must not be truncated
+    // concurrently; rustdoc writes its output before cargo exits, so built JSON is complete when it is loaded.
+    Ok(unsafe { memmap2::Mmap::map(&file)? })
+}
+
 #[derive(Debug)]
 pub enum BuildCrateError {
-    RustdocJson(rustdoc_json::BuildError),
+    /// The toolchain (or `cargo` itself) is not installed.
+    ToolchainNotFound {
+        toolchain: Option<String>,
+        message: String,
+    },
+    /// The manifest does not exist.
+    ManifestNotFound(PathBuf),
+    /// The crate failed to compile. `diagnostics` are the compiler messages (including warnings).
+    CompileFailed {
+        diagnostics: Vec<Diagnostic>,
+        stderr: String,
+    },
+    /// `cargo rustdoc` failed for another reason, e.g. the dependencies could not be resolved.
+    Cargo {
+        status: ExitStatus,
+        stderr: String,
+    },
+    CargoMetadata(cargo_metadata::Error),
+    /// The rustdoc JSON of a standard library crate was not found (see [`Crate::load_sysroot`]).
+    MissingSysrootJson {
+        path: PathBuf,
+        toolchain: Option<String>,
+    },
+    /// The rustdoc JSON has a format version that cannot be loaded (see [`compat`]).
+    /// `source` is the deserialization error, if the JSON was attempted to be loaded anyway.
+    FormatVersion {
+        expected: u32,
+        actual: u32,
+        source: Option<serde_json::Error>,
+    },
+    /// A git revision could not be checked out (see [`CrateBuilder::at_git_rev`]).
+    Git {
+        rev: String,
+        message: String,
+    },
+    /// The build was cancelled through a [`progress::CancellationToken`].
+    Cancelled,
+    /// The build did not finish within the [`CrateBuilder::timeout`].
+    TimedOut(Duration),
     Io(std::io::Error),
     Serde(serde_json::Error),
 }
 
+impl BuildCrateError {
+    /// The error diagnostics of a [`BuildCrateError::CompileFailed`].
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        let diagnostics = match self {
+            BuildCrateError::CompileFailed { diagnostics, .. } => &diagnostics[..],
+            _ => &[],
+        };
+        diagnostics
+            .iter()
+            .filter(|diag| matches!(diag.level, DiagnosticLevel::Error | DiagnosticLevel::Ice))
+    }
+}
+
 impl std::fmt::Display for BuildCrateError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            BuildCrateError::RustdocJson(err) => err.fmt(f),
+            BuildCrateError::ToolchainNotFound {
+                toolchain: Some(toolchain),
+                message,
+            } => write!(
+                f,
+                "toolchain `{toolchain}` not found ({message}); install it with `rustup toolchain install {toolchain}`"
+            ),
+            BuildCrateError::ToolchainNotFound {
+                toolchain: None,
+                message,
+            } => write!(f, "cargo not found ({message})"),
+            BuildCrateError::ManifestNotFound(path) => {
+                write!(f, "manifest {} not found", path.display())
+            }
+            BuildCrateError::CompileFailed { .. } => {
+                let mut errors = self.errors();
+                match errors.next() {
+                    Some(first) => {
+                        write!(f, "failed to compile: {}", first.message)?;
+                        let rest = errors.count();
+                        if rest > 0 {
+                            write!(f, " (and {rest} more errors)")?;
+                        }
+                        Ok(())
+                    }
+                    None => write!(f, "failed to compile"),
+                }
+            }
+            BuildCrateError::Cargo { status, stderr } => {
+                write!(f, "cargo rustdoc failed ({status})")?;
+                if let Some(line) = stderr.lines().find(|line| line.starts_with("error")) {
+                    write!(f, ": {line}")?;
+                }
+                Ok(())
+            }
+            BuildCrateError::CargoMetadata(err) => err.fmt(f),
+            BuildCrateError::MissingSysrootJson { path, toolchain } => write!(
+                f,
+                "{} not found; install it with `rustup component add{} rust-docs-json`",
+                path.display(),
+                toolchain
+                    .as_ref()
+                    .map(|toolchain| format!(" --toolchain {toolchain}"))
+                    .unwrap_or_default()
+            ),
+            BuildCrateError::FormatVersion {
+                expected, actual, ..
+            } => write!(
+                f,
+                "unsupported rustdoc JSON format version {actual} (expected {expected}, or {}..{expected} to be upgraded)",
+                compat::MIN_FORMAT_VERSION
+            ),
+            BuildCrateError::Git { rev, message } => {
+                write!(f, "failed to check out `{rev}`: {message}")
+            }
+            BuildCrateError::Cancelled => write!(f, "the build was cancelled"),
+            BuildCrateError::TimedOut(timeout) => {
+                write!(f, "the build timed out after {timeout:?}")
+            }
             BuildCrateError::Io(err) => err.fmt(f),
             BuildCrateError::Serde(err) => err.fmt(f),
         }
     }
 }
 
-impl std::error::Error for BuildCrateError {}
+impl std::error::Error for BuildCrateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildCrateError::CargoMetadata(err) => Some(err),
+            BuildCrateError::Io(err) => Some(err),
+            BuildCrateError::Serde(err) => Some(err),
+            BuildCrateError::FormatVersion {
+                source: Some(err), ..
+            } => Some(err),
+            _ => None,
+        }
+    }
+}
 
-impl From<rustdoc_json::BuildError> for BuildCrateError {
-    fn from(err: rustdoc_json::BuildError) -> Self {
-        Self::RustdocJson(err)
+impl From<cargo_metadata::Error> for BuildCrateError {
+    fn from(err: cargo_metadata::Error) -> Self {
+        Self::CargoMetadata(err)
     }
 }
 
@@ -1554,110 +2054,311 @@ impl From<serde_json::Error> for BuildCrateError {
     }
 }
 
-#[derive(Default)]
+/// Whether cargo colors its output, see [`CrateBuilder::color`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Color {
+    Always,
+    Never,
+    /// Color if the output is a terminal.
+    #[default]
+    Auto,
+}
+
+/// Builds the rustdoc JSON of a crate with `cargo rustdoc` and loads it.
+#[derive(Debug, Clone)]
 pub struct CrateBuilder {
-    builder: rustdoc_json::Builder,
+    toolchain: Option<String>,
+    manifest_path: PathBuf,
+    package: Option<String>,
+    target: Option<String>,
+    target_dir: Option<PathBuf>,
+    all_features: bool,
+    no_default_features: bool,
+    features: Vec<String>,
+    document_private_items: bool,
+    quiet: bool,
+    silent: bool,
+    color: Color,
+    cap_lints: Option<String>,
+    cache_dir: Option<PathBuf>,
+    source: Option<scaffold::Source>,
+    document_hidden_items: bool,
+    cfgs: Vec<String>,
+    rustdoc_flags: Vec<String>,
+    offline: bool,
+    locked: bool,
+    frozen: bool,
+    envs: Vec<(OsString, OsString)>,
+    cargo_path: Option<PathBuf>,
+    vendor_dir: Option<PathBuf>,
+    git_rev: Option<String>,
+    progress: Option<progress::ProgressCallback>,
+    timeout: Option<Duration>,
+    cancellation_token: Option<progress::CancellationToken>,
+    newer_format_versions: compat::NewerVersions,
+}
+
+impl Default for CrateBuilder {
+    fn default() -> Self {
+        Self {
+            toolchain: None,
+            manifest_path: PathBuf::from("Cargo.toml"),
+            package: None,
+            target: None,
+            target_dir: None,
+            all_features: false,
+            no_default_features: false,
+            features: vec![],
+            document_private_items: false,
+            quiet: false,
+            silent: false,
+            color: Color::Auto,
+            cap_lints: Some("warn".to_string()),
+            cache_dir: None,
+            source: None,
+            document_hidden_items: false,
+            cfgs: vec![],
+            rustdoc_flags: vec![],
+            offline: false,
+            locked: false,
+            frozen: false,
+            envs: vec![],
+            cargo_path: None,
+            vendor_dir: None,
+            git_rev: None,
+            progress: None,
+            timeout: None,
+            cancellation_token: None,
+            newer_format_versions: compat::NewerVersions::default(),
+        }
+    }
 }
 
 impl CrateBuilder {
     pub fn new() -> Self {
-        Self {
-            builder: rustdoc_json::Builder::default(),
-        }
+        Self::default()
     }
 
     pub fn toolchain(mut self, toolchain: impl Into<String>) -> Self {
-        self.builder = self.builder.toolchain(toolchain);
+        self.toolchain = Some(toolchain.into());
         self
     }
 
     pub fn manifest_path(mut self, manifest_path: impl AsRef<Path>) -> Self {
-        self.builder = self.builder.manifest_path(manifest_path);
+        self.manifest_path = manifest_path.as_ref().to_path_buf();
         self
     }
 
     pub fn all_features(mut self, all_features: bool) -> Self {
-        self.builder = self.builder.all_features(all_features);
+        self.all_features = all_features;
         self
     }
 
     pub fn package(mut self, package: impl AsRef<str>) -> Self {
-        self.builder = self.builder.package(package);
+        self.package = Some(package.as_ref().to_string());
         self
     }
 
     pub fn features(mut self, features: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
-        self.builder = self.builder.features(features);
+        self.features = features
+            .into_iter()
+            .map(|feature| feature.as_ref().to_string())
+            .collect();
         self
     }
 
     pub fn no_default_features(mut self, no_default_features: bool) -> Self {
-        self.builder = self.builder.no_default_features(no_default_features);
+        self.no_default_features = no_default_features;
         self
     }
 
     pub fn target(mut self, target: String) -> Self {
-        self.builder = self.builder.target(target);
+        self.target = Some(target);
         self
     }
 
     pub fn target_dir(mut self, target_dir: impl AsRef<Path>) -> Self {
-        self.builder = self.builder.target_dir(target_dir);
+        self.target_dir = Some(target_dir.as_ref().to_path_buf());
         self
     }
 
     pub fn document_private_items(mut self, document_private_items: bool) -> Self {
-        self.builder = self.builder.document_private_items(document_private_items);
+        self.document_private_items = document_private_items;
+        self
+    }
+
+    /// Include `#[doc(hidden)]` items (rustdoc's `--document-hidden-items`).
+    pub fn document_hidden_items(mut self, document_hidden_items: bool) -> Self {
+        self.document_hidden_items = document_hidden_items;
+        self
+    }
+
+    /// Pass `--cfg <cfg>` to rustdoc for the documented crate, e.g. `docsrs` or `feature="foo"`.
+    pub fn cfg(mut self, cfg: impl AsRef<str>) -> Self {
+        self.cfgs.push(cfg.as_ref().to_string());
+        self
+    }
+
+    /// Extra rustdoc flags, passed like `RUSTDOCFLAGS` (through `CARGO_ENCODED_RUSTDOCFLAGS`, so flags may contain spaces).
+    pub fn rustdoc_flags(mut self, flags: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        self.rustdoc_flags
+            .extend(flags.into_iter().map(|flag| flag.as_ref().to_string()));
+        self
+    }
+
+    /// Pass `--offline` to cargo, so that it never accesses the network.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Pass `--locked` to cargo, so that it fails instead of updating `Cargo.lock`.
+    pub fn locked(mut self, locked: bool) -> Self {
+        self.locked = locked;
+        self
+    }
+
+    /// Pass `--frozen` to cargo (`--locked` and `--offline`).
+    pub fn frozen(mut self, frozen: bool) -> Self {
+        self.frozen = frozen;
+        self
+    }
+
+    /// Set an environment variable for cargo (and the processes it runs).
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.envs
+            .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    /// Run this cargo binary instead of `cargo` from `PATH` (or `rustup run <toolchain> cargo`).
+    /// If a toolchain is set too, it is passed through the `RUSTUP_TOOLCHAIN` environment variable.
+    pub fn cargo_path(mut self, cargo_path: impl AsRef<Path>) -> Self {
+        self.cargo_path = Some(cargo_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Resolve crates.io dependencies from a directory created by `cargo vendor` instead of the registry.
+    pub fn vendor_dir(mut self, vendor_dir: impl AsRef<Path>) -> Self {
+        self.vendor_dir = Some(vendor_dir.as_ref().to_path_buf());
         self
     }
 
     pub fn quiet(mut self, quiet: bool) -> Self {
-        self.builder = self.builder.quiet(quiet);
+        self.quiet = quiet;
         self
     }
 
     pub fn silent(mut self, silent: bool) -> Self {
-        self.builder = self.builder.silent(silent);
+        self.silent = silent;
         self
     }
 
     pub fn color(mut self, color: Color) -> Self {
-        self.builder = self.builder.color(color);
+        self.color = color;
         self
     }
 
     pub fn color_always(mut self, color_always: bool) -> Self {
         if color_always {
-            self.builder = self.builder.color(Color::Always);
+            self.color = Color::Always;
         }
         self
     }
 
     pub fn color_never(mut self, color_never: bool) -> Self {
         if color_never {
-            self.builder = self.builder.color(Color::Never);
+            self.color = Color::Never;
         }
         self
     }
 
     pub fn cap_lints(mut self, cap_lints: Option<impl AsRef<str>>) -> Self {
-        self.builder = self.builder.cap_lints(cap_lints);
+        self.cap_lints = cap_lints.map(|cap_lints| cap_lints.as_ref().to_string());
+        self
+    }
+
+    /// Cache the rustdoc JSON in `cache_dir`, keyed by [`CrateBuilder::fingerprint`].
+    /// When nothing changed since a previous build, the cached JSON is loaded without running rustdoc.
+    pub fn cache_dir(mut self, cache_dir: impl AsRef<Path>) -> Self {
+        self.cache_dir = Some(cache_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Call `callback` with the [`progress::BuildEvent`]s of the build.
+    ///
+    /// ```no_run
+    /// use crate_inspector::CrateBuilder;
+    /// use crate_inspector::progress::BuildEvent;
+    ///
+    /// let krate = CrateBuilder::default()
+    ///     .toolchain("nightly")
+    ///     .silent(true)
+    ///     .on_progress(|event| match event {
+    ///         BuildEvent::Compiled { name, done, total: Some(total) } => {
+    ///             println!("compiled {name} ({done}/{total})")
+    ///         }
+    ///         BuildEvent::Documenting { name } => println!("documenting {name}"),
+    ///         _ => {}
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(&progress::BuildEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(progress::ProgressCallback::new(callback));
+        self
+    }
+
+    /// Kill cargo and fail with [`BuildCrateError::TimedOut`] if the build takes longer than `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Kill cargo and fail with [`BuildCrateError::Cancelled`] when `token` is cancelled.
+    pub fn cancellation_token(mut self, token: progress::CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// How to load rustdoc JSON of a format version newer than [`compat::FORMAT_VERSION`], e.g. built by a later
+    /// nightly (default: [`compat::NewerVersions::Try`]).
+    pub fn newer_format_versions(mut self, newer: compat::NewerVersions) -> Self {
+        self.newer_format_versions = newer;
         self
     }
 
     pub fn build(self) -> Result<Crate, BuildCrateError> {
-        let path = self.builder.build()?;
-        let krate = serde_json::from_reader(std::fs::File::open(path)?).map(Crate)?;
-        Ok(krate)
+        self.build_with(None, Crate::from_path_with)
     }
 
     pub fn build_with_captured_output(
         self,
-        stdout: impl Write,
-        stderr: impl Write,
+        mut stdout: impl Write,
+        mut stderr: impl Write,
     ) -> Result<Crate, BuildCrateError> {
-        let path = self.builder.build_with_captured_output(stdout, stderr)?;
-        let krate = serde_json::from_reader(std::fs::File::open(path)?).map(Crate)?;
-        Ok(krate)
+        self.build_with(Some((&mut stdout, &mut stderr)), Crate::from_path_with)
+    }
+
+    /// Like [`CrateBuilder::build`], but loads the crate as a [`lazy::LazyCrate`], which parses items on demand.
+    pub fn build_lazy(self) -> Result<lazy::LazyCrate, BuildCrateError> {
+        self.build_with(None, lazy::LazyCrate::from_path_with)
+    }
+
+    fn build_with<T>(
+        self,
+        output: Option<(&mut dyn Write, &mut dyn Write)>,
+        load: impl FnOnce(PathBuf, compat::NewerVersions) -> Result<T, BuildCrateError>,
+    ) -> Result<T, BuildCrateError> {
+        self.check_cancelled()?;
+        let (_scaffold, builder) = self.scaffold()?;
+        let path = builder.run_cached(output)?;
+        builder.emit(progress::BuildEvent::Parsing { path: path.clone() });
+        let loaded = load(path, builder.newer_format_versions)?;
+        builder.emit(progress::BuildEvent::Finished);
+        Ok(loaded)
     }
 }
@@ -0,0 +1,73 @@
+use rayon::prelude::*;
+
+use crate::*;
+
+macro_rules! impl_par_items {
+    ($($all: ident, $root: ident => $par_all: ident, $par_root: ident: $ty: ident;)*) => {
+        impl Crate {
+            $(
+                #[doc = concat!("Parallel version of [`Crate::", stringify!($all), "`].")]
+                pub fn $par_all(&self) -> impl ParallelIterator<Item = $ty<'_>> {
+                    self.par_all_items()
+                        .filter_map(|item| self.downcast::<$ty>(item))
+                }
+
+                #[doc = concat!("Parallel version of [`Crate::", stringify!($root), "`].")]
+                pub fn $par_root(&self) -> impl ParallelIterator<Item = $ty<'_>> {
+                    self.$par_all().filter(|item| item.is_root_item())
+                }
+            )*
+        }
+    };
+}
+
+impl Crate {
+    /// Parallel version of [`Crate::all_items`].
+    pub fn par_all_items(&self) -> impl ParallelIterator<Item = &rustdoc_types::Item> {
+        self.index.par_iter().map(|(_, item)| item)
+    }
+
+    /// Parallel version of [`Crate::items`].
+    pub fn par_items(&self) -> impl ParallelIterator<Item = &rustdoc_types::Item> {
+        self.par_all_items().filter(|item| item.crate_id == 0)
+    }
+
+    /// Parallel version of [`Crate::all_modules`].
+    pub fn par_all_modules(&self) -> impl ParallelIterator<Item = ModuleItem<'_>> {
+        self.par_all_items()
+            .filter_map(|item| self.downcast::<ModuleItem>(item))
+    }
+
+    /// Parallel version of [`Crate::modules`].
+    pub fn par_modules(&self) -> impl ParallelIterator<Item = ModuleItem<'_>> {
+        self.par_all_modules()
+            .filter(|module| module.is_crate_item())
+    }
+
+    /// Parallel version of [`Crate::all_functions`].
+    pub fn par_all_functions(&self) -> impl ParallelIterator<Item = FunctionItem<'_>> {
+        self.par_all_items()
+            .filter_map(|item| self.downcast::<FunctionItem>(item))
+    }
+
+    /// Parallel version of [`Crate::functions`].
+    pub fn par_functions(&self) -> impl ParallelIterator<Item = FunctionItem<'_>> {
+        self.par_all_functions().filter(|func| {
+            func.is_root_item() && !func.is_method() && !func.is_associated() && func.has_body()
+        })
+    }
+}
+
+impl_par_items! {
+    all_constants, constants => par_all_constants, par_constants: ConstantItem;
+    all_statics, statics => par_all_statics, par_statics: StaticItem;
+    all_structs, structs => par_all_structs, par_structs: StructItem;
+    all_traits, traits => par_all_traits, par_traits: TraitItem;
+    all_enums, enums => par_all_enums, par_enums: EnumItem;
+    all_type_aliases, type_aliases => par_all_type_aliases, par_type_aliases: TypeAliasItem;
+    all_trait_aliases, trait_aliases => par_all_trait_aliases, par_trait_aliases: TraitAliasItem;
+    all_unions, unions => par_all_unions, par_unions: UnionItem;
+    all_impls, impls => par_all_impls, par_impls: ImplItem;
+    all_macros, macros => par_all_macros, par_macros: MacroItem;
+    all_uses, uses => par_all_uses, par_uses: UseItem;
+}
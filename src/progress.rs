@@ -0,0 +1,161 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{BuildCrateError, CrateBuilder};
+
+/// A progress event of a build, delivered to the callback set with [`CrateBuilder::on_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BuildEvent {
+    /// The JSON was found in the build cache; cargo is not run.
+    Cached { path: PathBuf },
+    /// `cargo rustdoc` was started. `total` is an estimate of the number of dependencies to compile.
+    Started { total: Option<usize> },
+    /// A dependency was compiled. `done` counts the dependencies compiled (or found fresh) so far.
+    Compiled {
+        name: String,
+        done: usize,
+        total: Option<usize>,
+    },
+    /// rustdoc started documenting the crate (not reported with [`quiet`](crate::CrateBuilder::quiet)).
+    Documenting { name: String },
+    /// The rustdoc JSON is being loaded.
+    Parsing { path: PathBuf },
+    /// The crate was loaded.
+    Finished,
+}
+
+/// Cancels a build when [`CancellationToken::cancel`] is called, e.g. from another thread.
+///
+/// A running cargo process is killed and the build fails with [`BuildCrateError::Cancelled`].
+///
+/// ```no_run
+/// use crate_inspector::CrateBuilder;
+/// use crate_inspector::progress::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let builder = CrateBuilder::default()
+///     .toolchain("nightly")
+///     .cancellation_token(token.clone());
+/// let handle = std::thread::spawn(move || builder.build());
+/// token.cancel();
+/// assert!(handle.join().unwrap().is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(Arc<dyn Fn(&BuildEvent) + Send + Sync>);
+
+impl ProgressCallback {
+    pub(crate) fn new(callback: impl Fn(&BuildEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn emit(&self, event: BuildEvent) {
+        (self.0)(&event);
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+impl CrateBuilder {
+    pub(crate) fn emit(&self, event: BuildEvent) {
+        if let Some(progress) = &self.progress {
+            progress.emit(event);
+        }
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<(), BuildCrateError> {
+        match &self.cancellation_token {
+            Some(token) if token.is_cancelled() => Err(BuildCrateError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Starts a thread that kills `child` when the build is cancelled or the timeout expires.
+    /// Returns `None` if neither a cancellation token nor a timeout is set.
+    pub(crate) fn watch(&self, child: &Arc<Mutex<Child>>, started: Instant) -> Option<Watchdog> {
+        if self.cancellation_token.is_none() && self.timeout.is_none() {
+            return None;
+        }
+        let token = self.cancellation_token.clone().unwrap_or_default();
+        let timeout = self.timeout;
+        let child = Arc::clone(child);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = std::thread::spawn({
+            let stop = Arc::clone(&stop);
+            move || loop {
+                let interrupt = if token.is_cancelled() {
+                    BuildCrateError::Cancelled
+                } else if let Some(timeout) =
+                    timeout.filter(|timeout| started.elapsed() >= *timeout)
+                {
+                    BuildCrateError::TimedOut(timeout)
+                } else if stop.load(Ordering::SeqCst) {
+                    return None;
+                } else {
+                    std::thread::sleep(Duration::from_millis(20));
+                    continue;
+                };
+                kill(&mut child.lock().unwrap_or_else(|err| err.into_inner()));
+                return Some(interrupt);
+            }
+        });
+        Some(Watchdog { stop, thread })
+    }
+}
+
+/// The thread started by [`CrateBuilder::watch`].
+pub(crate) struct Watchdog {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Option<BuildCrateError>>,
+}
+
+impl Watchdog {
+    /// Stops the thread, returning the error if it killed the process.
+    pub(crate) fn finish(self) -> Option<BuildCrateError> {
+        self.stop.store(true, Ordering::SeqCst);
+        self.thread.join().ok().flatten()
+    }
+}
+
+/// Kills `child` and everything it started, and waits for it.
+///
+/// On Unix, cargo is spawned as the leader of its own process group by `CrateBuilder::run`, so that the whole
+/// group (`rustup`, `cargo`, `rustc`, build scripts, ...) is killed. The group is signalled before the leader is
+/// reaped, so its id cannot have been reused. Elsewhere, only the spawned process itself is killed.
+pub(crate) fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(None) = child.try_wait() {
+        // SAFETY: `kill` has no memory safety preconditions
+        unsafe {
+            libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
@@ -0,0 +1,410 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use rustdoc_types::{Id, Item, ItemEnum, StructKind, VariantKind, Visibility};
+
+use crate::compat::FORMAT_VERSION;
+use crate::util::visit_item_paths;
+use crate::{is_doc_hidden, BuildCrateError, Crate};
+
+/// Builds a trimmed copy of a crate, created by [`Crate::prune`].
+///
+/// The result is a valid rustdoc JSON crate: the items are renumbered from `0`, and lists of item ids
+/// (module items, fields, variants, impls, ...) only contain the items that are kept. Links and re-exports of
+/// removed items are dropped. Types and visibilities may still refer to removed items; those ids keep their entry in
+/// `paths`, or their item if they have none, so that every id resolves (except those of other crates without
+/// [`Pruner::external_summaries`]).
+///
+/// ```no_run
+/// use crate_inspector::CrateBuilder;
+///
+/// let krate = CrateBuilder::default().toolchain("nightly").build().unwrap();
+/// let pruned = krate
+///     .prune()
+///     .public_only(true)
+///     .module("my_crate::api")
+///     .external_summaries(false)
+///     .build()
+///     .unwrap();
+/// pruned.save_json("api.json").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pruner<'a> {
+    krate: &'a Crate,
+    public_only: bool,
+    module: Option<String>,
+    external_summaries: bool,
+}
+
+impl Crate {
+    /// Start building a trimmed copy of the crate. See [`Pruner`].
+    pub fn prune(&self) -> Pruner<'_> {
+        Pruner {
+            krate: self,
+            public_only: false,
+            module: None,
+            external_summaries: true,
+        }
+    }
+
+    /// Write the crate as rustdoc JSON (of [`FORMAT_VERSION`], the layout it is stored in).
+    pub fn write_json(&self, writer: impl Write) -> Result<(), BuildCrateError> {
+        let krate = if self.format_version == FORMAT_VERSION {
+            Cow::Borrowed(&self.krate)
+        } else {
+            // Loaded from a newer format version
+            let mut krate = self.krate.clone();
+            krate.format_version = FORMAT_VERSION;
+            Cow::Owned(krate)
+        };
+        serde_json::to_writer(writer, &krate)?;
+        Ok(())
+    }
+
+    /// Save the crate as a rustdoc JSON file. See [`Crate::write_json`].
+    pub fn save_json(&self, path: impl AsRef<Path>) -> Result<(), BuildCrateError> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        self.write_json(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Pruner<'_> {
+    /// Keep only the items of the public API (see [`Crate::public_api`]).
+    pub fn public_only(mut self, public_only: bool) -> Self {
+        self.public_only = public_only;
+        self
+    }
+
+    /// Keep only the subtree of a module, e.g. `my_crate::inner`. The modules leading to it are kept, with only
+    /// the module on the way in their items.
+    pub fn module(mut self, path: impl Into<String>) -> Self {
+        self.module = Some(path.into());
+        self
+    }
+
+    /// Keep the `paths` entries and index items of other crates (`true` by default).
+    ///
+    /// Without them, the impls of their traits (e.g. the auto trait impls of local types) are removed as well,
+    /// but types may still refer to their items.
+    pub fn external_summaries(mut self, external_summaries: bool) -> Self {
+        self.external_summaries = external_summaries;
+        self
+    }
+
+    /// Build the trimmed crate. Returns `None` if the [`Pruner::module`] does not exist.
+    pub fn build(&self) -> Option<Crate> {
+        let krate = self.krate;
+        let selected = match &self.module {
+            Some(path) => {
+                let module = krate.get_item_by_path(path)?;
+                if !matches!(module.inner, ItemEnum::Module(_)) {
+                    return None;
+                }
+                module.id
+            }
+            None => krate.root,
+        };
+        let mut chain = HashSet::new();
+        let mut current = selected;
+        while let Some(parent) = krate.parent_id(&current) {
+            chain.insert(*parent);
+            current = *parent;
+        }
+
+        let kept = self.kept(selected, &chain);
+        let paths = krate
+            .paths
+            .iter()
+            .filter(|(_, summary)| self.external_summaries || summary.crate_id == 0)
+            .collect::<HashMap<_, _>>();
+        let resolvable = |id: &Id| kept.contains(id) || paths.contains_key(id);
+
+        // Items that are referenced by the kept ones (e.g. in types or `pub(in ..)` visibilities) but have no `paths`
+        // entry are added to the index as well, without being listed in their modules
+        let mut items = HashMap::new();
+        let mut pending = kept.iter().copied().collect::<Vec<_>>();
+        while let Some(id) = pending.pop() {
+            if items.contains_key(&id) {
+                continue;
+            }
+            let Some(item) = krate.index.get(&id) else {
+                continue;
+            };
+            let mut item = item.clone();
+            retain_kept(&mut item, &kept, &resolvable);
+            visit_item_ids(&mut item, &mut |id| {
+                if !resolvable(id) && (self.external_summaries || is_local(krate, id)) {
+                    pending.push(*id);
+                }
+            });
+            items.insert(id, item);
+        }
+        let mut items = items.into_values().collect::<Vec<_>>();
+        let mut referenced = HashSet::from([krate.root]);
+        for item in &mut items {
+            visit_item_ids(item, &mut |id| {
+                referenced.insert(*id);
+            });
+        }
+
+        // Renumber in the order of the original ids, so that the output is deterministic
+        let mut ids = referenced.into_iter().collect::<Vec<_>>();
+        ids.sort_by_key(|id| id.0);
+        let remap = ids
+            .iter()
+            .enumerate()
+            .map(|(new, old)| (*old, Id(new as u32)))
+            .collect::<HashMap<_, _>>();
+        let index = items
+            .into_iter()
+            .map(|mut item| {
+                visit_item_ids(&mut item, &mut |id| *id = remap[id]);
+                (item.id, item)
+            })
+            .collect();
+        let paths = paths
+            .into_iter()
+            .filter_map(|(id, summary)| Some((*remap.get(id)?, summary.clone())))
+            .collect();
+        let external_crates = if self.external_summaries {
+            krate.external_crates.clone()
+        } else {
+            HashMap::new()
+        };
+        Some(Crate::new(rustdoc_types::Crate {
+            root: remap[&krate.root],
+            crate_version: krate.crate_version.clone(),
+            includes_private: krate.includes_private && !self.public_only,
+            index,
+            paths,
+            external_crates,
+            target: krate.target.clone(),
+            format_version: FORMAT_VERSION,
+        }))
+    }
+
+    /// The ids of the items to keep: the modules of `chain`, and the subtree of `selected`.
+    fn kept(&self, selected: Id, chain: &HashSet<Id>) -> HashSet<Id> {
+        let krate = self.krate;
+        let public = self.public_only.then(|| krate.public_api());
+        let mut kept = HashSet::new();
+        let mut stack = vec![krate.root];
+        while let Some(id) = stack.pop() {
+            let Some(item) = krate.index.get(&id) else {
+                continue;
+            };
+            if !kept.insert(id) {
+                continue;
+            }
+            if chain.contains(&id) {
+                if let ItemEnum::Module(module) = &item.inner {
+                    stack.extend(
+                        module
+                            .items
+                            .iter()
+                            .filter(|child| chain.contains(child) || **child == selected),
+                    );
+                }
+                continue;
+            }
+            let mut children = children(item);
+            if let (Some(public), ItemEnum::Use(use_)) = (public, &item.inner) {
+                // `pub use foo::*;` exposes the public items of `foo`, but not `foo` itself
+                let glob_module = use_
+                    .id
+                    .filter(|target| use_.is_glob && !public.contains(target))
+                    .and_then(|target| krate.index.get(&target));
+                if let Some(ItemEnum::Module(module)) = glob_module.map(|module| &module.inner) {
+                    children.extend(&module.items);
+                }
+            }
+            stack.extend(
+                children
+                    .into_iter()
+                    .filter(|child| {
+                        self.external_summaries
+                            || (is_local(krate, child) && !is_foreign_trait_impl(krate, child))
+                    })
+                    .filter(|child| {
+                        public.is_none_or(|public| {
+                            public.contains(child) || is_public_use(krate, child)
+                        })
+                    }),
+            );
+        }
+        kept
+    }
+}
+
+/// Whether `id` is a `pub use` (re-exports are not part of [`Crate::public_api`], only their targets).
+fn is_public_use(krate: &Crate, id: &Id) -> bool {
+    krate.index.get(id).is_some_and(|item| {
+        matches!(item.inner, ItemEnum::Use(_))
+            && item.visibility == Visibility::Public
+            && !is_doc_hidden(item)
+    })
+}
+
+fn is_local(krate: &Crate, id: &Id) -> bool {
+    krate.index.get(id).is_some_and(|item| item.crate_id == 0)
+}
+
+/// Whether `id` is an impl of a trait of another crate.
+fn is_foreign_trait_impl(krate: &Crate, id: &Id) -> bool {
+    let Some(ItemEnum::Impl(impl_)) = krate.index.get(id).map(|item| &item.inner) else {
+        return false;
+    };
+    impl_.trait_.as_ref().is_some_and(|trait_| {
+        let crate_id = match krate.index.get(&trait_.id) {
+            Some(item) => Some(item.crate_id),
+            None => krate.paths.get(&trait_.id).map(|summary| summary.crate_id),
+        };
+        crate_id != Some(0)
+    })
+}
+
+/// The items nested in `item` (or re-exported by it).
+fn children(item: &Item) -> Vec<Id> {
+    let mut children = vec![];
+    match &item.inner {
+        ItemEnum::Module(module) => children.extend(&module.items),
+        ItemEnum::Use(use_) => children.extend(use_.id),
+        ItemEnum::Struct(struct_) => {
+            match &struct_.kind {
+                StructKind::Unit => {}
+                StructKind::Tuple(fields) => children.extend(fields.iter().flatten()),
+                StructKind::Plain { fields, .. } => children.extend(fields),
+            }
+            children.extend(&struct_.impls);
+        }
+        ItemEnum::Union(union) => {
+            children.extend(&union.fields);
+            children.extend(&union.impls);
+        }
+        ItemEnum::Enum(enum_) => {
+            children.extend(&enum_.variants);
+            children.extend(&enum_.impls);
+        }
+        ItemEnum::Variant(variant) => match &variant.kind {
+            VariantKind::Plain => {}
+            VariantKind::Tuple(fields) => children.extend(fields.iter().flatten()),
+            VariantKind::Struct { fields, .. } => children.extend(fields),
+        },
+        ItemEnum::Trait(trait_) => children.extend(&trait_.items),
+        ItemEnum::Impl(impl_) => children.extend(&impl_.items),
+        ItemEnum::Primitive(primitive) => children.extend(&primitive.impls),
+        _ => {}
+    }
+    children
+}
+
+/// Removes the ids of items that are not kept from the id lists of `item`, and unresolvable links and re-exports.
+fn retain_kept(item: &mut Item, kept: &HashSet<Id>, resolvable: &impl Fn(&Id) -> bool) {
+    item.links.retain(|_, id| resolvable(id));
+    let retain = |ids: &mut Vec<Id>| ids.retain(|id| kept.contains(id));
+    match &mut item.inner {
+        ItemEnum::Module(module) => retain(&mut module.items),
+        ItemEnum::Use(use_) => use_.id = use_.id.filter(|id| resolvable(id)),
+        ItemEnum::Struct(struct_) => {
+            match &mut struct_.kind {
+                StructKind::Unit => {}
+                StructKind::Tuple(fields) => {
+                    for field in fields {
+                        *field = field.filter(|id| kept.contains(id));
+                    }
+                }
+                StructKind::Plain {
+                    fields,
+                    has_stripped_fields,
+                } => {
+                    let len = fields.len();
+                    retain(fields);
+                    *has_stripped_fields |= fields.len() < len;
+                }
+            }
+            retain(&mut struct_.impls);
+        }
+        ItemEnum::Union(union) => {
+            let len = union.fields.len();
+            retain(&mut union.fields);
+            union.has_stripped_fields |= union.fields.len() < len;
+            retain(&mut union.impls);
+        }
+        ItemEnum::Enum(enum_) => {
+            let len = enum_.variants.len();
+            retain(&mut enum_.variants);
+            enum_.has_stripped_variants |= enum_.variants.len() < len;
+            retain(&mut enum_.impls);
+        }
+        ItemEnum::Variant(variant) => match &mut variant.kind {
+            VariantKind::Plain => {}
+            VariantKind::Tuple(fields) => {
+                for field in fields {
+                    *field = field.filter(|id| kept.contains(id));
+                }
+            }
+            VariantKind::Struct {
+                fields,
+                has_stripped_fields,
+            } => {
+                let len = fields.len();
+                retain(fields);
+                *has_stripped_fields |= fields.len() < len;
+            }
+        },
+        ItemEnum::Trait(trait_) => {
+            retain(&mut trait_.items);
+            retain(&mut trait_.implementations);
+        }
+        ItemEnum::Impl(impl_) => retain(&mut impl_.items),
+        ItemEnum::Primitive(primitive) => retain(&mut primitive.impls),
+        _ => {}
+    }
+}
+
+/// Calls `f` on every id in `item`.
+fn visit_item_ids(item: &mut Item, f: &mut impl FnMut(&mut Id)) {
+    f(&mut item.id);
+    item.links.values_mut().for_each(&mut *f);
+    if let Visibility::Restricted { parent, .. } = &mut item.visibility {
+        f(parent);
+    }
+    match &mut item.inner {
+        ItemEnum::Module(module) => module.items.iter_mut().for_each(&mut *f),
+        ItemEnum::Use(use_) => use_.id.iter_mut().for_each(&mut *f),
+        ItemEnum::Union(union) => {
+            union.fields.iter_mut().for_each(&mut *f);
+            union.impls.iter_mut().for_each(&mut *f);
+        }
+        ItemEnum::Struct(struct_) => {
+            match &mut struct_.kind {
+                StructKind::Unit => {}
+                StructKind::Tuple(fields) => fields.iter_mut().flatten().for_each(&mut *f),
+                StructKind::Plain { fields, .. } => fields.iter_mut().for_each(&mut *f),
+            }
+            struct_.impls.iter_mut().for_each(&mut *f);
+        }
+        ItemEnum::Enum(enum_) => {
+            enum_.variants.iter_mut().for_each(&mut *f);
+            enum_.impls.iter_mut().for_each(&mut *f);
+        }
+        ItemEnum::Variant(variant) => match &mut variant.kind {
+            VariantKind::Plain => {}
+            VariantKind::Tuple(fields) => fields.iter_mut().flatten().for_each(&mut *f),
+            VariantKind::Struct { fields, .. } => fields.iter_mut().for_each(&mut *f),
+        },
+        ItemEnum::Trait(trait_) => {
+            trait_.items.iter_mut().for_each(&mut *f);
+            trait_.implementations.iter_mut().for_each(&mut *f);
+        }
+        ItemEnum::Impl(impl_) => impl_.items.iter_mut().for_each(&mut *f),
+        ItemEnum::Primitive(primitive) => primitive.impls.iter_mut().for_each(&mut *f),
+        _ => {}
+    }
+    visit_item_paths(item, |path| f(&mut path.id));
+}
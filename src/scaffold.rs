@@ -0,0 +1,369 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tempfile::TempDir;
+use toml_edit::{table, value, DocumentMut, Item, TableLike};
+
+use crate::{BuildCrateError, CrateBuilder};
+
+/// The source of a crate without a Cargo project, see [`CrateBuilder::from_source`], [`CrateBuilder::from_file`],
+/// [`CrateBuilder::from_crate_archive`] and [`CrateBuilder::from_vendor_dir`].
+#[derive(Debug, Clone)]
+pub(crate) enum Source {
+    Code(String),
+    File(PathBuf),
+    Archive(PathBuf),
+    Vendored { name: String, version: String },
+}
+
+impl CrateBuilder {
+    /// Document inline source code as the `lib.rs` of a crate named `snippet`.
+    ///
+    /// A temporary Cargo project is scaffolded for the build and removed afterwards, so the code cannot have dependencies.
+    ///
+    /// ```no_run
+    /// use crate_inspector::CrateBuilder;
+    ///
+    /// let krate = CrateBuilder::from_source("pub struct Foo;")
+    ///     .toolchain("nightly")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(krate.structs().next().unwrap().name(), "Foo");
+    /// ```
+    pub fn from_source(code: impl Into<String>) -> Self {
+        Self {
+            source: Some(Source::Code(code.into())),
+            ..Self::default()
+        }
+    }
+
+    /// Document a single `.rs` file as the root of a library crate named after the file.
+    ///
+    /// Modules declared in the file are resolved relative to it, as for a `lib.rs`.
+    /// Like [`CrateBuilder::from_source`], a temporary Cargo project is used for the build.
+    pub fn from_file(path: impl AsRef<Path>) -> Self {
+        Self {
+            source: Some(Source::File(path.as_ref().to_path_buf())),
+            ..Self::default()
+        }
+    }
+
+    /// Document a packaged crate (a `.crate` file, as downloaded from a registry or created by `cargo package`).
+    ///
+    /// The archive is unpacked into a temporary directory and built `--offline`, so its dependencies must already be
+    /// in cargo's cache, or in a [`CrateBuilder::vendor_dir`]. Dev-dependencies are ignored.
+    ///
+    /// ```no_run
+    /// use crate_inspector::CrateBuilder;
+    ///
+    /// let krate = CrateBuilder::from_crate_archive("mirror/serde-1.0.219.crate")
+    ///     .toolchain("nightly")
+    ///     .vendor_dir("vendor")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn from_crate_archive(path: impl AsRef<Path>) -> Self {
+        Self {
+            source: Some(Source::Archive(path.as_ref().to_path_buf())),
+            offline: true,
+            ..Self::default()
+        }
+    }
+
+    /// Document the crate `name` at `version` from a directory created by `cargo vendor` (`vendor` by default,
+    /// see [`CrateBuilder::vendor_dir`]), resolving its dependencies from the same directory.
+    ///
+    /// The crate is copied into a temporary directory and built `--offline`. Dev-dependencies are ignored.
+    pub fn from_vendor_dir(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            source: Some(Source::Vendored {
+                name: name.into(),
+                version: version.into(),
+            }),
+            offline: true,
+            vendor_dir: Some(PathBuf::from("vendor")),
+            ..Self::default()
+        }
+    }
+
+    /// Document the crate as of a git revision (a commit, branch or tag, e.g. `v1.2.0`) of the repository containing
+    /// the manifest.
+    ///
+    /// The revision is checked out into a temporary worktree (`git worktree add --detach`), which is removed after the build,
+    /// so the working tree of the repository is left untouched. Unless a [`CrateBuilder::target_dir`] is set, the build
+    /// uses a fresh target directory.
+    ///
+    /// ```no_run
+    /// use crate_inspector::CrateBuilder;
+    ///
+    /// let builder = CrateBuilder::default().toolchain("nightly").silent(true);
+    /// let old = builder.clone().at_git_rev("v1.2.0").build().unwrap();
+    /// let new = builder.build().unwrap();
+    /// ```
+    pub fn at_git_rev(mut self, rev: impl Into<String>) -> Self {
+        self.git_rev = Some(rev.into());
+        self
+    }
+
+    /// Writes the temporary project of a `from_*` builder (or checks out the worktree of [`CrateBuilder::at_git_rev`])
+    /// and returns a builder for its manifest. The project is removed when the returned [`Scaffold`] is dropped.
+    pub(crate) fn scaffold(self) -> Result<(Option<Scaffold>, Self), BuildCrateError> {
+        let Some(source) = &self.source else {
+            return match self.git_rev.clone() {
+                Some(rev) => self.checkout(&rev),
+                None => Ok((None, self)),
+            };
+        };
+        let dir = tempdir()?;
+        let manifest_path = match source {
+            Source::Code(code) => {
+                fs::create_dir(dir.path().join("src"))?;
+                fs::write(dir.path().join("src").join("lib.rs"), code)?;
+                write_manifest(dir.path(), "snippet", Path::new("src/lib.rs"))?
+            }
+            Source::File(path) => {
+                if !path.is_file() {
+                    return Err(not_found(format!("{} not found", path.display())));
+                }
+                write_manifest(dir.path(), &crate_name(path), &std::path::absolute(path)?)?
+            }
+            Source::Archive(path) => {
+                let archive = fs::File::open(path)?;
+                tar::Archive::new(flate2::read::GzDecoder::new(archive)).unpack(dir.path())?;
+                // `<name>-<version>/Cargo.toml`
+                let package_dir = fs::read_dir(dir.path())?
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .find(|path| path.join("Cargo.toml").is_file())
+                    .ok_or_else(|| {
+                        not_found(format!("no Cargo.toml found in {}", path.display()))
+                    })?;
+                strip_dev_dependencies(&package_dir.join("Cargo.toml"))?
+            }
+            Source::Vendored { name, version } => {
+                let vendor_dir = self.vendor_dir.as_deref().unwrap_or(Path::new("vendor"));
+                let vendored = find_vendored(vendor_dir, name, version)?;
+                let package_dir = dir.path().join(format!("{name}-{version}"));
+                copy_dir(&vendored, &package_dir)?;
+                strip_dev_dependencies(&package_dir.join("Cargo.toml"))?
+            }
+        };
+
+        let target_dir = self
+            .target_dir
+            .clone()
+            .unwrap_or_else(|| dir.path().join("target"));
+        let builder = Self {
+            source: None,
+            package: None,
+            git_rev: None,
+            ..self
+        }
+        .manifest_path(manifest_path)
+        .target_dir(target_dir);
+        Ok((Some(Scaffold { dir, repo: None }), builder))
+    }
+
+    /// Checks out `rev` into a temporary worktree and returns a builder for the manifest at the same relative path.
+    fn checkout(self, rev: &str) -> Result<(Option<Scaffold>, Self), BuildCrateError> {
+        let manifest_path = std::path::absolute(&self.manifest_path)?;
+        let manifest_dir = manifest_path.parent().unwrap_or(Path::new("/"));
+        let manifest_dir = fs::canonicalize(manifest_dir)
+            .map_err(|_| BuildCrateError::ManifestNotFound(self.manifest_path.clone()))?;
+        let toplevel = git(&manifest_dir, rev, &["rev-parse", "--show-toplevel"])?;
+        let repo = PathBuf::from(toplevel.trim_end());
+        let relative = manifest_dir
+            .strip_prefix(&repo)
+            .unwrap_or(Path::new(""))
+            .join(manifest_path.file_name().unwrap_or("Cargo.toml".as_ref()));
+
+        // Resolve the revision first, so that e.g. `--orphan` cannot be taken as an option of `git worktree add`
+        let spec = format!("{rev}^{{commit}}");
+        let commit = git(
+            &repo,
+            rev,
+            &["rev-parse", "--verify", "--end-of-options", &spec],
+        )?;
+        let commit = commit.trim_end();
+
+        let dir = tempdir()?;
+        let worktree = dir.path().join("worktree");
+        let worktree_arg = worktree.to_string_lossy();
+        git(
+            &repo,
+            rev,
+            &[
+                "worktree",
+                "add",
+                "--detach",
+                "--quiet",
+                "--",
+                &worktree_arg,
+                commit,
+            ],
+        )?;
+        let scaffold = Scaffold {
+            dir,
+            repo: Some(repo),
+        };
+
+        let target_dir = self
+            .target_dir
+            .clone()
+            .unwrap_or_else(|| scaffold.dir.path().join("target"));
+        let builder = Self {
+            git_rev: None,
+            ..self
+        }
+        .manifest_path(worktree.join(relative))
+        .target_dir(target_dir);
+        Ok((Some(scaffold), builder))
+    }
+}
+
+/// The temporary directory of a scaffolded build. Dropping it removes the directory, and the worktree from its repository.
+pub(crate) struct Scaffold {
+    dir: TempDir,
+    repo: Option<PathBuf>,
+}
+
+impl Drop for Scaffold {
+    fn drop(&mut self) {
+        if let Some(repo) = &self.repo {
+            let _ = Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args(["worktree", "remove", "--force"])
+                .arg(self.dir.path().join("worktree"))
+                .output();
+        }
+    }
+}
+
+fn tempdir() -> std::io::Result<TempDir> {
+    tempfile::Builder::new()
+        .prefix("crate-inspector-")
+        .tempdir()
+}
+
+/// Runs `git -C <dir> <args>` and returns its stdout.
+fn git(dir: &Path, rev: &str, args: &[&str]) -> Result<String, BuildCrateError> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        return Err(BuildCrateError::Git {
+            rev: rev.to_string(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn not_found(message: String) -> BuildCrateError {
+    std::io::Error::new(std::io::ErrorKind::NotFound, message).into()
+}
+
+/// Writes the manifest of a standalone package (with an empty `[workspace]`, so that it is not mistaken for a member
+/// of an enclosing workspace) and returns its path.
+fn write_manifest(dir: &Path, name: &str, lib_path: &Path) -> Result<PathBuf, BuildCrateError> {
+    let mut manifest = DocumentMut::new();
+    manifest["package"] = table();
+    manifest["package"]["name"] = value(name);
+    manifest["package"]["version"] = value("0.0.0");
+    manifest["package"]["edition"] = value("2021");
+    manifest["lib"] = table();
+    manifest["lib"]["path"] = value(lib_path.to_string_lossy().into_owned());
+    manifest["workspace"] = table();
+    let manifest_path = dir.join("Cargo.toml");
+    fs::write(&manifest_path, manifest.to_string())?;
+    Ok(manifest_path)
+}
+
+/// Removes the dev-dependencies of a packaged manifest (including target-specific ones), which are not vendored and not
+/// needed for rustdoc, and marks the package as the root of its own workspace. Returns the manifest's path.
+fn strip_dev_dependencies(manifest_path: &Path) -> Result<PathBuf, BuildCrateError> {
+    let mut manifest = parse_manifest(manifest_path)?;
+    let root = manifest.as_table_mut();
+    remove_dev_dependencies(root);
+    if let Some(targets) = root.get_mut("target").and_then(Item::as_table_like_mut) {
+        for (_, target) in targets.iter_mut() {
+            if let Some(target) = target.as_table_like_mut() {
+                remove_dev_dependencies(target);
+            }
+        }
+    }
+    if !root.contains_key("workspace") {
+        root.insert("workspace", table());
+    }
+    fs::write(manifest_path, manifest.to_string())?;
+    Ok(manifest_path.to_path_buf())
+}
+
+fn remove_dev_dependencies(table: &mut dyn TableLike) {
+    table.remove("dev-dependencies");
+    table.remove("dev_dependencies");
+}
+
+fn parse_manifest(manifest_path: &Path) -> Result<DocumentMut, BuildCrateError> {
+    fs::read_to_string(manifest_path)?.parse().map_err(|err| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("failed to parse {}: {err}", manifest_path.display()),
+        )
+        .into()
+    })
+}
+
+/// The directory of `name` at `version` in a vendor directory: `<name>-<version>` (`cargo vendor --versioned-dirs`),
+/// or `<name>` if its version matches.
+fn find_vendored(vendor_dir: &Path, name: &str, version: &str) -> Result<PathBuf, BuildCrateError> {
+    let versioned = vendor_dir.join(format!("{name}-{version}"));
+    if versioned.join("Cargo.toml").is_file() {
+        return Ok(versioned);
+    }
+    let unversioned = vendor_dir.join(name);
+    if let Ok(manifest) = parse_manifest(&unversioned.join("Cargo.toml")) {
+        if package_version(&manifest) == Some(version) {
+            return Ok(unversioned);
+        }
+    }
+    Err(not_found(format!(
+        "{name} {version} not found in {}",
+        vendor_dir.display()
+    )))
+}
+
+/// The `version` of the `[package]` table of a manifest. `None` if it is inherited from the workspace.
+fn package_version(manifest: &DocumentMut) -> Option<&str> {
+    manifest.get("package")?.get("version")?.as_str()
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), BuildCrateError> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// A valid package name derived from the file stem, e.g. `my-file.rs` -> `my_file`.
+fn crate_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("_{name}")
+    }
+}
@@ -0,0 +1,142 @@
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::compat::{self, FORMAT_VERSION};
+use crate::{BuildCrateError, Crate};
+
+/// The first bytes of a snapshot file.
+const MAGIC: &[u8; 8] = b"CRISNAP\0";
+/// The version of the snapshot encoding, bumped when the header or the encoding changes.
+pub const SNAPSHOT_VERSION: u32 = 2;
+/// The maximum size of an encoded [`SnapshotHeader`].
+const HEADER_LIMIT: u64 = 64 * 1024;
+
+/// The header of a snapshot written by [`Crate::save_snapshot`], which can be read without loading the crate.
+///
+/// A snapshot file consists of a magic number, the [`SNAPSHOT_VERSION`], this header and the crate,
+/// encoded with [bincode](https://docs.rs/bincode/1). The crate is stored with the layout of the `rustdoc_types`
+/// this library is built with, so snapshots can only be loaded if [`SnapshotHeader::format_version`] is [`FORMAT_VERSION`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotHeader {
+    format_version: u32,
+    fingerprint: Option<String>,
+}
+
+impl SnapshotHeader {
+    /// Read the header of a snapshot file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, BuildCrateError> {
+        read_header(&mut BufReader::new(fs::File::open(path)?))
+    }
+
+    /// The rustdoc JSON format version of the encoded crate.
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    /// The fingerprint the snapshot was saved with, e.g. [`CrateBuilder::fingerprint`](crate::CrateBuilder::fingerprint).
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.fingerprint.as_deref()
+    }
+}
+
+fn invalid_data(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> BuildCrateError {
+    io::Error::new(io::ErrorKind::InvalidData, err).into()
+}
+
+/// The bincode options of snapshots. Readers add a size limit, so that a corrupted length cannot exhaust the memory.
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+fn read_header(reader: &mut impl Read) -> Result<SnapshotHeader, BuildCrateError> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a crate snapshot"));
+    }
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != SNAPSHOT_VERSION {
+        return Err(invalid_data(format!(
+            "unsupported snapshot version {version} (expected {SNAPSHOT_VERSION})"
+        )));
+    }
+    options()
+        .with_limit(HEADER_LIMIT)
+        .deserialize_from(reader)
+        .map_err(invalid_data)
+}
+
+impl Crate {
+    /// Save the crate in a compact binary format, which is several times faster to load than rustdoc JSON.
+    ///
+    /// `fingerprint` is stored in the [`SnapshotHeader`], so that stale snapshots can be detected without loading them.
+    ///
+    /// ```no_run
+    /// use crate_inspector::{Crate, CrateBuilder};
+    /// use crate_inspector::snapshot::SnapshotHeader;
+    ///
+    /// let builder = CrateBuilder::default().toolchain("nightly");
+    /// let fingerprint = builder.fingerprint().unwrap();
+    /// let krate = match SnapshotHeader::read("api.snapshot") {
+    ///     Ok(header) if header.fingerprint() == Some(&fingerprint) => Crate::load_snapshot("api.snapshot").unwrap(),
+    ///     _ => {
+    ///         let krate = builder.build().unwrap();
+    ///         krate.save_snapshot("api.snapshot", Some(&fingerprint)).unwrap();
+    ///         krate
+    ///     }
+    /// };
+    /// ```
+    pub fn save_snapshot(
+        &self,
+        path: impl AsRef<Path>,
+        fingerprint: Option<&str>,
+    ) -> Result<(), BuildCrateError> {
+        let header = SnapshotHeader {
+            format_version: FORMAT_VERSION,
+            fingerprint: fingerprint.map(str::to_string),
+        };
+        // Written to a temporary file that replaces `path` when complete, so that readers never see a partial snapshot
+        let path = path.as_ref();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut writer = BufWriter::new(tempfile::NamedTempFile::new_in(dir)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        options()
+            .serialize_into(&mut writer, &header)
+            .map_err(invalid_data)?;
+        options()
+            .serialize_into(&mut writer, &self.krate)
+            .map_err(invalid_data)?;
+        let file = writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        file.persist(path).map_err(|err| err.error)?;
+        Ok(())
+    }
+
+    /// Load a crate saved by [`Crate::save_snapshot`].
+    pub fn load_snapshot(path: impl AsRef<Path>) -> Result<Self, BuildCrateError> {
+        let file = fs::File::open(path)?;
+        // The crate cannot be larger than the file
+        let limit = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let header = read_header(&mut reader)?;
+        if header.format_version != FORMAT_VERSION {
+            return Err(compat::unsupported(header.format_version, None));
+        }
+        let krate = options()
+            .with_limit(limit)
+            .deserialize_from(reader)
+            .map_err(invalid_data)?;
+        Ok(Crate::new(krate))
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use rustdoc_types::{GenericParamDef, GenericParamDefKind, Id, Item, ItemEnum, ItemKind, Path};
+use serde::{Deserialize, Serialize};
+
+use crate::format::{generics_to_string, path_to_string, type_to_string, where_clause_to_string};
+use crate::util::{visit_generics, visit_path, visit_type, Visitor};
+use crate::{item_kind, Crate};
+
+/// An identifier of an item that does not depend on the build, unlike [`Id`]s, which change between builds and toolchains.
+///
+/// It consists of the name of the crate defining the item, the canonical path of the item within the crate, and its kind
+/// (items of different namespaces may share a path). Fields, variants and the items of traits and impls are identified by
+/// the path of their parent plus their name. Impls have no path; they are identified by their [`ImplKey`] instead, which
+/// their items inherit.
+///
+/// Stable ids are computed with [`Crate::stable_id`] and resolved with [`Crate::by_stable_id`].
+///
+/// ```no_run
+/// use crate_inspector::CrateBuilder;
+///
+/// let krate = CrateBuilder::default().toolchain("nightly").build().unwrap();
+/// let item = krate.get_item_by_path("my_crate::Foo").unwrap();
+/// let stable_id = krate.stable_id(&item.id).unwrap();
+/// let json = serde_json::to_string(&stable_id).unwrap();
+///
+/// let rebuilt = CrateBuilder::default().toolchain("nightly").build().unwrap();
+/// let item = rebuilt.by_stable_id(&serde_json::from_str(&json).unwrap()).unwrap();
+/// assert_eq!(item.name.as_deref(), Some("Foo"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StableId {
+    krate: String,
+    path: Vec<String>,
+    kind: ItemKind,
+    impl_: Option<ImplKey>,
+}
+
+/// Identifies an impl by what it implements, for which type and with which generics.
+///
+/// Paths are rendered canonically (e.g. `core::clone::Clone` rather than `Clone`) and generic parameters are renamed by
+/// position (`impl<T0> core::clone::Clone for my_crate::Foo<T0>`), so that the key does not depend on how the
+/// impl is written. Several inherent impl blocks of a type with the same generics share a key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ImplKey {
+    trait_: Option<String>,
+    for_: String,
+    generics: String,
+    where_clause: String,
+}
+
+impl StableId {
+    /// The name of the crate defining the item.
+    pub fn krate(&self) -> &str {
+        &self.krate
+    }
+
+    /// The path of the item within the crate, relative to the impl for items of impls.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    pub fn kind(&self) -> ItemKind {
+        self.kind
+    }
+
+    /// The impl of the item, or the impl itself.
+    pub fn impl_(&self) -> Option<&ImplKey> {
+        self.impl_.as_ref()
+    }
+}
+
+impl ImplKey {
+    /// The canonical path of the implemented trait, with its generic arguments. `None` for inherent impls.
+    pub fn trait_(&self) -> Option<&str> {
+        self.trait_.as_deref()
+    }
+
+    /// The type the impl is for, with canonical paths.
+    pub fn for_(&self) -> &str {
+        &self.for_
+    }
+
+    /// The generic parameters of the impl with their bounds, e.g. `<T0: Clone>`.
+    pub fn generics(&self) -> &str {
+        &self.generics
+    }
+
+    /// The where clause of the impl, e.g. `where T0: Send`, or an empty string.
+    pub fn where_clause(&self) -> &str {
+        &self.where_clause
+    }
+}
+
+impl fmt::Display for StableId {
+    /// e.g. `Struct my_crate::a::Foo` or `Function my_crate::<impl<T0> core::clone::Clone for my_crate::a::Foo<T0>>::clone`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.kind, self.krate)?;
+        if let Some(impl_) = &self.impl_ {
+            write!(f, "::<{impl_}>")?;
+        }
+        for segment in &self.path {
+            write!(f, "::{segment}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ImplKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "impl{} ", self.generics)?;
+        if let Some(trait_) = &self.trait_ {
+            write!(f, "{trait_} for ")?;
+        }
+        write!(f, "{}", self.for_)?;
+        if !self.where_clause.is_empty() {
+            write!(f, " {}", self.where_clause)?;
+        }
+        Ok(())
+    }
+}
+
+/// Canonicalizes the paths of an impl and renames its generic parameters by position (`T0`, `T1`, ..., or `'t0`, ...
+/// for lifetimes), so that its [`ImplKey`] does not depend on how the impl is written.
+struct ImplKeyVisitor<'a> {
+    krate: &'a Crate,
+    names: HashMap<String, String>,
+}
+
+impl<'a> ImplKeyVisitor<'a> {
+    fn new(krate: &'a Crate, params: &[GenericParamDef]) -> Self {
+        let names = params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| {
+                let name = match param.kind {
+                    GenericParamDefKind::Lifetime { .. } => format!("'t{i}"),
+                    _ => format!("T{i}"),
+                };
+                (param.name.clone(), name)
+            })
+            .collect();
+        Self { krate, names }
+    }
+}
+
+impl Visitor for ImplKeyVisitor<'_> {
+    fn path(&mut self, path: &mut Path) {
+        self.krate.canonicalize(path);
+    }
+
+    fn name(&mut self, name: &mut String) {
+        if let Some(renamed) = self.names.get(name) {
+            name.clone_from(renamed);
+        }
+    }
+}
+
+impl Crate {
+    /// The [`StableId`] of the item `id`, or `None` if it is not in the index or `paths` of the crate.
+    pub fn stable_id(&self, id: &Id) -> Option<StableId> {
+        let item = self.index.get(id);
+        if let Some(summary) = self.paths.get(id) {
+            let (krate, path) = summary.path.split_first()?;
+            return Some(StableId {
+                krate: krate.clone(),
+                path: path.to_vec(),
+                kind: item.map_or(summary.kind, item_kind),
+                impl_: None,
+            });
+        }
+        let item = item?;
+        if let ItemEnum::Impl(impl_) = &item.inner {
+            let mut visitor = ImplKeyVisitor::new(self, &impl_.generics.params);
+            let trait_ = impl_.trait_.as_ref().map(|trait_| {
+                let mut trait_ = trait_.clone();
+                visit_path(&mut trait_, &mut visitor);
+                path_to_string(&trait_)
+            });
+            let mut for_ = impl_.for_.clone();
+            visit_type(&mut for_, &mut visitor);
+            let mut generics = impl_.generics.clone();
+            visit_generics(&mut generics, &mut visitor);
+            return Some(StableId {
+                krate: self.crate_name(item)?.to_string(),
+                path: vec![],
+                kind: ItemKind::Impl,
+                impl_: Some(ImplKey {
+                    trait_,
+                    for_: type_to_string(&for_),
+                    generics: generics_to_string(&generics),
+                    where_clause: where_clause_to_string(&generics),
+                }),
+            });
+        }
+        let mut stable_id = self.stable_id(self.parent_id(id)?)?;
+        stable_id.path.push(item.name.clone()?);
+        stable_id.kind = item_kind(item);
+        Some(stable_id)
+    }
+
+    /// Get the item with the given [`StableId`], e.g. one computed for an earlier build of the crate.
+    ///
+    /// The stable ids of all items are computed on first use. If several inherent impl blocks share an [`ImplKey`],
+    /// the first one is returned.
+    pub fn by_stable_id(&self, stable_id: &StableId) -> Option<&Item> {
+        let index = self.stable_ids.get_or_init(|| {
+            let mut index = HashMap::<StableId, Id>::new();
+            for id in self.index.keys() {
+                if let Some(stable_id) = self.stable_id(id) {
+                    index
+                        .entry(stable_id)
+                        .and_modify(|first| *first = (*first).min(*id))
+                        .or_insert(*id);
+                }
+            }
+            index
+        });
+        index.get(stable_id).and_then(|id| self.index.get(id))
+    }
+
+    fn crate_name(&self, item: &Item) -> Option<&str> {
+        if item.crate_id == 0 {
+            Some(self.name())
+        } else {
+            self.external_crates
+                .get(&item.crate_id)
+                .map(|krate| krate.name.as_str())
+        }
+    }
+}
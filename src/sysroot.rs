@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::compat::NewerVersions;
+use crate::{BuildCrateError, Crate};
+
+/// The crates shipped as rustdoc JSON by the `rust-docs-json` component.
+pub(crate) const SYSROOT_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+
+impl Crate {
+    /// Load a crate of the standard library (`std`, `core`, `alloc`, ...) of the active toolchain.
+    ///
+    /// This requires the `rust-docs-json` component, which is only available on nightly toolchains.
+    /// See [`Crate::load_sysroot_for`] to select the toolchain.
+    pub fn load_sysroot(name: &str) -> Result<Self, BuildCrateError> {
+        Self::load_sysroot_with(None, name, NewerVersions::default())
+    }
+
+    /// Load a crate of the standard library of `toolchain` (e.g. `"nightly"`).
+    ///
+    /// The JSON is read from `share/doc/rust/json/<name>.json` in the toolchain's sysroot.
+    /// If it is not there, [`BuildCrateError::MissingSysrootJson`] is returned;
+    /// install it with `rustup component add --toolchain <toolchain> rust-docs-json`.
+    pub fn load_sysroot_for(toolchain: &str, name: &str) -> Result<Self, BuildCrateError> {
+        Self::load_sysroot_with(Some(toolchain), name, NewerVersions::default())
+    }
+
+    /// Like [`Crate::load_sysroot_for`] (or [`Crate::load_sysroot`] if `toolchain` is `None`), loading newer format
+    /// versions according to `newer`. The JSON of a nightly toolchain is often newer than [`compat::FORMAT_VERSION`].
+    ///
+    /// [`compat::FORMAT_VERSION`]: crate::compat::FORMAT_VERSION
+    pub fn load_sysroot_with(
+        toolchain: Option<&str>,
+        name: &str,
+        newer: NewerVersions,
+    ) -> Result<Self, BuildCrateError> {
+        Self::load_sysroot_json(&sysroot(toolchain)?, toolchain, name, newer)
+    }
+
+    /// Load a crate of the standard library from the sysroot directory `sysroot`, e.g. of a toolchain that is not
+    /// managed by rustup. See [`Crate::load_sysroot_for`].
+    pub fn load_sysroot_from(
+        sysroot: impl AsRef<Path>,
+        name: &str,
+        newer: NewerVersions,
+    ) -> Result<Self, BuildCrateError> {
+        Self::load_sysroot_json(sysroot.as_ref(), None, name, newer)
+    }
+
+    fn load_sysroot_json(
+        sysroot: &Path,
+        toolchain: Option<&str>,
+        name: &str,
+        newer: NewerVersions,
+    ) -> Result<Self, BuildCrateError> {
+        let path = sysroot
+            .join("share")
+            .join("doc")
+            .join("rust")
+            .join("json")
+            .join(format!("{name}.json"));
+        if !path.is_file() {
+            return Err(BuildCrateError::MissingSysrootJson {
+                path,
+                toolchain: toolchain.map(str::to_string),
+            });
+        }
+        Crate::from_path_with(path, newer)
+    }
+}
+
+fn sysroot(toolchain: Option<&str>) -> Result<PathBuf, BuildCrateError> {
+    let mut command = match toolchain {
+        Some(toolchain) => {
+            let mut command = Command::new("rustup");
+            command.args(["run", toolchain, "rustc"]);
+            command
+        }
+        None => Command::new("rustc"),
+    };
+    let output = command.args(["--print", "sysroot"]).output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "`{command:?}` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .into());
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
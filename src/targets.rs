@@ -0,0 +1,318 @@
+use rustdoc_types::{
+    GenericBound, Generics, Id, Item, ItemEnum, ItemKind, StructKind, VariantKind,
+};
+
+use crate::api::{merge_api_paths, MergedPath};
+use crate::format::{
+    bound_to_string, fn_sig_to_string, generics_to_string, type_to_string, where_clause_to_string,
+};
+use crate::{AnyItem, BuildCrateError, Crate, CrateBuilder};
+
+/// Builds a crate for several target triples and compares the results in a [`TargetComparison`].
+///
+/// Building for a target requires its standard library (`rustup target add --toolchain <toolchain> <target>`);
+/// targets that fail to build are collected into [`TargetComparison::errors`].
+///
+/// ```no_run
+/// use crate_inspector::CrateBuilder;
+/// use crate_inspector::targets::TargetComparisonBuilder;
+///
+/// let comparison = TargetComparisonBuilder::default()
+///     .crate_builder(CrateBuilder::default().toolchain("nightly").silent(true))
+///     .targets(["x86_64-unknown-linux-gnu", "wasm32-unknown-unknown", "x86_64-pc-windows-msvc"])
+///     .build();
+/// for item in comparison.partial_items() {
+///     println!("{} is only available on {:?}", item.path(), item.targets().collect::<Vec<_>>());
+/// }
+/// for item in comparison.differing_items() {
+///     println!("{} differs: {:?}", item.path(), item.signatures());
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct TargetComparisonBuilder {
+    builder: CrateBuilder,
+    targets: Vec<String>,
+}
+
+impl TargetComparisonBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The builder used as a template for each target (its target is overridden).
+    pub fn crate_builder(mut self, builder: CrateBuilder) -> Self {
+        self.builder = builder;
+        self
+    }
+
+    pub fn targets(mut self, targets: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        self.targets
+            .extend(targets.into_iter().map(|t| t.as_ref().to_string()));
+        self
+    }
+
+    pub fn build(self) -> TargetComparison {
+        let mut crates = vec![];
+        let mut errors = vec![];
+        for target in self.targets {
+            match self.builder.clone().target(target.clone()).build() {
+                Ok(krate) => crates.push((target, krate)),
+                Err(err) => errors.push((target, err)),
+            }
+        }
+        let mut comparison = TargetComparison::new(crates);
+        comparison.errors = errors;
+        comparison
+    }
+}
+
+/// The builds of a crate for several targets, created by [`TargetComparisonBuilder::build`] or [`TargetComparison::new`].
+///
+/// Items are identified across the builds by their public path and kind (see [`TargetItem`]).
+#[derive(Debug)]
+pub struct TargetComparison {
+    crates: Vec<(String, Crate)>,
+    errors: Vec<(String, BuildCrateError)>,
+    items: Vec<MergedPath>,
+}
+
+impl TargetComparison {
+    /// Compare existing builds of the same crate, e.g. rustdoc JSON produced on different machines, keyed by target.
+    pub fn new(crates: impl IntoIterator<Item = (String, Crate)>) -> Self {
+        let crates = crates.into_iter().collect::<Vec<_>>();
+        let items = merge_api_paths(&crates.iter().map(|(_, krate)| krate).collect::<Vec<_>>());
+        Self {
+            crates,
+            errors: vec![],
+            items,
+        }
+    }
+
+    /// The targets that were built successfully.
+    pub fn targets(&self) -> impl Iterator<Item = &str> {
+        self.crates.iter().map(|(target, _)| target.as_str())
+    }
+
+    /// Get the build of a target.
+    pub fn get(&self, target: &str) -> Option<&Crate> {
+        self.crates
+            .iter()
+            .find(|(built, _)| built == target)
+            .map(|(_, krate)| krate)
+    }
+
+    pub fn crates(&self) -> impl Iterator<Item = (&str, &Crate)> {
+        self.crates
+            .iter()
+            .map(|(target, krate)| (target.as_str(), krate))
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &BuildCrateError)> {
+        self.errors
+            .iter()
+            .map(|(target, err)| (target.as_str(), err))
+    }
+
+    /// The public items of all builds, sorted by path.
+    pub fn items(&self) -> impl Iterator<Item = TargetItem<'_>> {
+        self.items.iter().map(|merged| TargetItem {
+            comparison: self,
+            merged,
+        })
+    }
+
+    /// Get an item by its path, e.g. `my_crate::foo::Bar` or `my_crate::Bar::method`.
+    pub fn get_item(&self, path: &str) -> Option<TargetItem<'_>> {
+        self.items().find(|item| item.path() == path)
+    }
+
+    /// The items that are missing on some targets.
+    pub fn partial_items(&self) -> impl Iterator<Item = TargetItem<'_>> {
+        self.items().filter(|item| !item.is_on_all_targets())
+    }
+
+    /// The items whose signature differs between the targets they are present on.
+    pub fn differing_items(&self) -> impl Iterator<Item = TargetItem<'_>> {
+        self.items().filter(|item| item.has_differing_signatures())
+    }
+}
+
+/// A public path of a [`TargetComparison`] and the targets it is present on.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetItem<'a> {
+    comparison: &'a TargetComparison,
+    merged: &'a MergedPath,
+}
+
+impl<'a> TargetItem<'a> {
+    pub fn path(&self) -> &'a str {
+        &self.merged.path
+    }
+
+    pub fn kind(&self) -> ItemKind {
+        self.merged.kind
+    }
+
+    fn present(&self) -> impl Iterator<Item = (&'a str, &'a Item)> {
+        self.comparison
+            .crates
+            .iter()
+            .zip(&self.merged.ids)
+            .filter_map(|((target, krate), id)| Some((target.as_str(), &krate.index[id.as_ref()?])))
+    }
+
+    /// The targets the item is present on.
+    pub fn targets(&self) -> impl Iterator<Item = &'a str> {
+        self.present().map(|(target, _)| target)
+    }
+
+    pub fn is_on_all_targets(&self) -> bool {
+        self.merged.ids.iter().all(Option::is_some)
+    }
+
+    /// The rendered signature of the item on each target it is present on.
+    ///
+    /// This covers function signatures, generics, the types of fields, statics and type aliases,
+    /// and the values of constants and enum discriminants.
+    pub fn signatures(&self) -> Vec<(&'a str, String)> {
+        self.present()
+            .map(|(target, item)| (target, signature(item)))
+            .collect()
+    }
+
+    pub fn has_differing_signatures(&self) -> bool {
+        let signatures = self.signatures();
+        signatures
+            .iter()
+            .any(|(_, signature)| signature != &signatures[0].1)
+    }
+
+    /// The item in the build of `target`, if present.
+    pub fn item_on(&self, target: &str) -> Option<AnyItem<'a>> {
+        let (_, item) = self.present().find(|(present, _)| *present == target)?;
+        let krate = self.comparison.get(target)?;
+        Some(krate.any_item(item))
+    }
+}
+
+/// `generics_to_string` followed by the where clause, if any.
+fn generics_with_where_clause(generics: &Generics) -> String {
+    format!("{}{}", generics_to_string(generics), where_suffix(generics))
+}
+
+fn where_suffix(generics: &Generics) -> String {
+    let where_clause = where_clause_to_string(generics);
+    if where_clause.is_empty() {
+        where_clause
+    } else {
+        format!(" {where_clause}")
+    }
+}
+
+/// `: A + B`, or nothing without bounds.
+fn bounds_suffix(bounds: &[GenericBound]) -> String {
+    if bounds.is_empty() {
+        return String::new();
+    }
+    let bounds = bounds.iter().map(bound_to_string).collect::<Vec<_>>();
+    format!(": {}", bounds.join(" + "))
+}
+
+/// The shape of a struct: nothing (unit), `(_, _)` (tuple) or ` { .. }` (plain).
+/// The fields themselves are items with their own signatures.
+fn struct_shape(kind: &StructKind) -> String {
+    match kind {
+        StructKind::Unit => String::new(),
+        StructKind::Tuple(fields) => tuple_shape(fields),
+        StructKind::Plain { .. } => " { .. }".to_string(),
+    }
+}
+
+/// Like [`struct_shape`].
+fn variant_shape(kind: &VariantKind) -> String {
+    match kind {
+        VariantKind::Plain => String::new(),
+        VariantKind::Tuple(fields) => tuple_shape(fields),
+        VariantKind::Struct { .. } => " { .. }".to_string(),
+    }
+}
+
+fn tuple_shape(fields: &[Option<Id>]) -> String {
+    format!("({})", vec!["_"; fields.len()].join(", "))
+}
+
+/// The parts of an item's declaration that can differ between targets, rendered as a string.
+pub(crate) fn signature(item: &Item) -> String {
+    match &item.inner {
+        ItemEnum::Function(func) => {
+            let header = &func.header;
+            format!(
+                "{}{}{}extern {:?} fn{}{}{}",
+                if header.is_const { "const " } else { "" },
+                if header.is_async { "async " } else { "" },
+                if header.is_unsafe { "unsafe " } else { "" },
+                header.abi,
+                generics_to_string(&func.generics),
+                fn_sig_to_string(&func.sig),
+                where_suffix(&func.generics)
+            )
+        }
+        ItemEnum::StructField(ty) => type_to_string(ty),
+        ItemEnum::Static(static_) => format!(
+            "{}{}",
+            if static_.is_mutable { "mut " } else { "" },
+            type_to_string(&static_.type_)
+        ),
+        ItemEnum::Constant { type_, const_ } => format!(
+            "{} = {}",
+            type_to_string(type_),
+            const_.value.as_ref().unwrap_or(&const_.expr)
+        ),
+        ItemEnum::AssocConst { type_, value } => match value {
+            Some(value) => format!("{} = {value}", type_to_string(type_)),
+            None => type_to_string(type_),
+        },
+        ItemEnum::TypeAlias(alias) => format!(
+            "{} = {}",
+            generics_with_where_clause(&alias.generics),
+            type_to_string(&alias.type_)
+        ),
+        ItemEnum::AssocType {
+            generics,
+            bounds,
+            type_,
+        } => format!(
+            "{}{}{} = {}",
+            generics_to_string(generics),
+            bounds_suffix(bounds),
+            where_suffix(generics),
+            type_.as_ref().map(type_to_string).unwrap_or_default()
+        ),
+        ItemEnum::Variant(variant) => format!(
+            "{}{}",
+            variant_shape(&variant.kind),
+            variant
+                .discriminant
+                .as_ref()
+                .map(|discriminant| format!(" = {}", discriminant.value))
+                .unwrap_or_default()
+        ),
+        ItemEnum::Struct(struct_) => format!(
+            "{}{}{}",
+            generics_to_string(&struct_.generics),
+            struct_shape(&struct_.kind),
+            where_suffix(&struct_.generics)
+        ),
+        ItemEnum::Enum(enum_) => generics_with_where_clause(&enum_.generics),
+        ItemEnum::Union(union) => generics_with_where_clause(&union.generics),
+        ItemEnum::Trait(trait_) => format!(
+            "{}{}trait{}{}{}",
+            if trait_.is_unsafe { "unsafe " } else { "" },
+            if trait_.is_auto { "auto " } else { "" },
+            generics_to_string(&trait_.generics),
+            bounds_suffix(&trait_.bounds),
+            where_suffix(&trait_.generics)
+        ),
+        _ => String::new(),
+    }
+}
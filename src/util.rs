@@ -0,0 +1,285 @@
+use rustdoc_types::{
+    AssocItemConstraintKind, GenericArg, GenericArgs, GenericBound, GenericParamDef,
+    GenericParamDefKind, Generics, Item, ItemEnum, Path, PreciseCapturingArg, Term, Type,
+    WherePredicate,
+};
+use sha2::{Digest, Sha256};
+
+use crate::Crate;
+
+impl Crate {
+    /// Replace the path as written with the canonical path of the item, if known.
+    pub(crate) fn canonicalize(&self, path: &mut Path) {
+        if let Some(summary) = self.paths.get(&path.id) {
+            path.path = summary.path.join("::");
+        }
+    }
+}
+
+/// Hashes `bytes` with their length, so that consecutive fields cannot run into each other.
+pub(crate) fn update(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_le_bytes());
+    hasher.update(bytes);
+}
+
+/// What the `visit_*` functions call on the parts of types, generics and bounds.
+pub(crate) trait Visitor {
+    fn path(&mut self, _path: &mut Path) {}
+
+    /// A name that may refer to a generic parameter: the declaration of a parameter, a generic type, a lifetime or a
+    /// const argument.
+    fn name(&mut self, _name: &mut String) {}
+}
+
+struct PathVisitor<F>(F);
+
+impl<F: FnMut(&mut Path)> Visitor for PathVisitor<F> {
+    fn path(&mut self, path: &mut Path) {
+        (self.0)(path)
+    }
+}
+
+/// Calls `f` on every path in the types, generics and bounds of `item`.
+pub(crate) fn visit_item_paths(item: &mut Item, f: impl FnMut(&mut Path)) {
+    visit_item(item, &mut PathVisitor(f));
+}
+
+fn visit_item(item: &mut Item, f: &mut impl Visitor) {
+    match &mut item.inner {
+        ItemEnum::Module(_)
+        | ItemEnum::ExternCrate { .. }
+        | ItemEnum::ExternType
+        | ItemEnum::Macro(_)
+        | ItemEnum::ProcMacro(_)
+        | ItemEnum::Use(_)
+        | ItemEnum::Variant(_)
+        | ItemEnum::Primitive(_) => {}
+        ItemEnum::Union(union) => visit_generics(&mut union.generics, f),
+        ItemEnum::Struct(struct_) => visit_generics(&mut struct_.generics, f),
+        ItemEnum::StructField(type_) => visit_type(type_, f),
+        ItemEnum::Enum(enum_) => visit_generics(&mut enum_.generics, f),
+        ItemEnum::Function(function) => {
+            visit_generics(&mut function.generics, f);
+            for (_, input) in &mut function.sig.inputs {
+                visit_type(input, f);
+            }
+            if let Some(output) = &mut function.sig.output {
+                visit_type(output, f);
+            }
+        }
+        ItemEnum::Trait(trait_) => {
+            visit_generics(&mut trait_.generics, f);
+            visit_bounds(&mut trait_.bounds, f);
+        }
+        ItemEnum::TraitAlias(alias) => {
+            visit_generics(&mut alias.generics, f);
+            visit_bounds(&mut alias.params, f);
+        }
+        ItemEnum::Impl(impl_) => {
+            visit_generics(&mut impl_.generics, f);
+            if let Some(trait_) = &mut impl_.trait_ {
+                visit_path(trait_, f);
+            }
+            visit_type(&mut impl_.for_, f);
+            if let Some(blanket_impl) = &mut impl_.blanket_impl {
+                visit_type(blanket_impl, f);
+            }
+        }
+        ItemEnum::TypeAlias(alias) => {
+            visit_generics(&mut alias.generics, f);
+            visit_type(&mut alias.type_, f);
+        }
+        ItemEnum::Constant { type_, .. } | ItemEnum::AssocConst { type_, .. } => {
+            visit_type(type_, f)
+        }
+        ItemEnum::Static(static_) => visit_type(&mut static_.type_, f),
+        ItemEnum::AssocType {
+            generics,
+            bounds,
+            type_,
+        } => {
+            visit_generics(generics, f);
+            visit_bounds(bounds, f);
+            if let Some(type_) = type_ {
+                visit_type(type_, f);
+            }
+        }
+    }
+}
+
+pub(crate) fn visit_generics(generics: &mut Generics, f: &mut impl Visitor) {
+    visit_params(&mut generics.params, f);
+    for predicate in &mut generics.where_predicates {
+        match predicate {
+            WherePredicate::BoundPredicate {
+                type_,
+                bounds,
+                generic_params,
+            } => {
+                visit_type(type_, f);
+                visit_bounds(bounds, f);
+                visit_params(generic_params, f);
+            }
+            WherePredicate::LifetimePredicate { lifetime, outlives } => {
+                f.name(lifetime);
+                outlives.iter_mut().for_each(|lifetime| f.name(lifetime));
+            }
+            WherePredicate::EqPredicate { lhs, rhs } => {
+                visit_type(lhs, f);
+                visit_term(rhs, f);
+            }
+        }
+    }
+}
+
+fn visit_params(params: &mut [GenericParamDef], f: &mut impl Visitor) {
+    for param in params {
+        f.name(&mut param.name);
+        match &mut param.kind {
+            GenericParamDefKind::Lifetime { outlives } => {
+                outlives.iter_mut().for_each(|lifetime| f.name(lifetime));
+            }
+            GenericParamDefKind::Type {
+                bounds, default, ..
+            } => {
+                visit_bounds(bounds, f);
+                if let Some(default) = default {
+                    visit_type(default, f);
+                }
+            }
+            GenericParamDefKind::Const { type_, .. } => visit_type(type_, f),
+        }
+    }
+}
+
+fn visit_bounds(bounds: &mut [GenericBound], f: &mut impl Visitor) {
+    for bound in bounds {
+        match bound {
+            GenericBound::TraitBound {
+                trait_,
+                generic_params,
+                ..
+            } => {
+                visit_path(trait_, f);
+                visit_params(generic_params, f);
+            }
+            GenericBound::Outlives(lifetime) => f.name(lifetime),
+            GenericBound::Use(args) => {
+                for arg in args {
+                    match arg {
+                        PreciseCapturingArg::Lifetime(name) | PreciseCapturingArg::Param(name) => {
+                            f.name(name)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn visit_term(term: &mut Term, f: &mut impl Visitor) {
+    if let Term::Type(type_) = term {
+        visit_type(type_, f);
+    }
+}
+
+pub(crate) fn visit_path(path: &mut Path, f: &mut impl Visitor) {
+    f.path(path);
+    if let Some(args) = &mut path.args {
+        visit_args(args, f);
+    }
+}
+
+fn visit_args(args: &mut GenericArgs, f: &mut impl Visitor) {
+    match args {
+        GenericArgs::AngleBracketed { args, constraints } => {
+            for arg in args {
+                match arg {
+                    GenericArg::Lifetime(lifetime) => f.name(lifetime),
+                    GenericArg::Type(type_) => visit_type(type_, f),
+                    GenericArg::Const(constant) => f.name(&mut constant.expr),
+                    GenericArg::Infer => {}
+                }
+            }
+            for constraint in constraints {
+                if let Some(args) = &mut constraint.args {
+                    visit_args(args, f);
+                }
+                match &mut constraint.binding {
+                    AssocItemConstraintKind::Equality(term) => visit_term(term, f),
+                    AssocItemConstraintKind::Constraint(bounds) => visit_bounds(bounds, f),
+                }
+            }
+        }
+        GenericArgs::Parenthesized { inputs, output } => {
+            for input in inputs {
+                visit_type(input, f);
+            }
+            if let Some(output) = output {
+                visit_type(output, f);
+            }
+        }
+        GenericArgs::ReturnTypeNotation => {}
+    }
+}
+
+pub(crate) fn visit_type(type_: &mut Type, f: &mut impl Visitor) {
+    match type_ {
+        Type::ResolvedPath(path) => visit_path(path, f),
+        Type::DynTrait(dyn_trait) => {
+            for poly in &mut dyn_trait.traits {
+                visit_path(&mut poly.trait_, f);
+                visit_params(&mut poly.generic_params, f);
+            }
+            if let Some(lifetime) = &mut dyn_trait.lifetime {
+                f.name(lifetime);
+            }
+        }
+        Type::Generic(name) => f.name(name),
+        Type::Primitive(_) | Type::Infer => {}
+        Type::FunctionPointer(pointer) => {
+            visit_params(&mut pointer.generic_params, f);
+            for (_, input) in &mut pointer.sig.inputs {
+                visit_type(input, f);
+            }
+            if let Some(output) = &mut pointer.sig.output {
+                visit_type(output, f);
+            }
+        }
+        Type::Tuple(types) => {
+            for type_ in types {
+                visit_type(type_, f);
+            }
+        }
+        Type::Array { type_, len } => {
+            visit_type(type_, f);
+            f.name(len);
+        }
+        Type::BorrowedRef {
+            lifetime, type_, ..
+        } => {
+            if let Some(lifetime) = lifetime {
+                f.name(lifetime);
+            }
+            visit_type(type_, f);
+        }
+        Type::Slice(type_) | Type::Pat { type_, .. } | Type::RawPointer { type_, .. } => {
+            visit_type(type_, f)
+        }
+        Type::ImplTrait(bounds) => visit_bounds(bounds, f),
+        Type::QualifiedPath {
+            args,
+            self_type,
+            trait_,
+            ..
+        } => {
+            if let Some(args) = args {
+                visit_args(args, f);
+            }
+            visit_type(self_type, f);
+            if let Some(trait_) = trait_ {
+                visit_path(trait_, f);
+            }
+        }
+    }
+}
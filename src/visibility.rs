@@ -0,0 +1,128 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use rustdoc_types::{Id, Item, ItemEnum, StructKind, Type, VariantKind, Visibility};
+
+use crate::Crate;
+
+/// Maps each item to the module, type, trait or impl that directly contains it.
+///
+/// An item listed by several modules (e.g. an inlined re-export) is mapped to its owner, see [`owner`].
+pub(crate) fn parents(krate: &Crate) -> HashMap<Id, Id> {
+    let mut candidates = HashMap::<Id, Vec<Id>>::new();
+    for item in krate.index.values() {
+        let children: Vec<&Id> = match &item.inner {
+            ItemEnum::Module(module) => module.items.iter().collect(),
+            ItemEnum::Struct(struct_) => match &struct_.kind {
+                StructKind::Unit => vec![],
+                StructKind::Tuple(fields) => fields.iter().flatten().collect(),
+                StructKind::Plain { fields, .. } => fields.iter().collect(),
+            },
+            ItemEnum::Union(union) => union.fields.iter().collect(),
+            ItemEnum::Enum(enum_) => enum_.variants.iter().collect(),
+            ItemEnum::Variant(variant) => match &variant.kind {
+                VariantKind::Plain => vec![],
+                VariantKind::Tuple(fields) => fields.iter().flatten().collect(),
+                VariantKind::Struct { fields, .. } => fields.iter().collect(),
+            },
+            ItemEnum::Trait(trait_) => trait_.items.iter().collect(),
+            ItemEnum::Impl(impl_) => impl_.items.iter().collect(),
+            _ => vec![],
+        };
+        for child in children {
+            candidates.entry(*child).or_default().push(item.id);
+        }
+    }
+    candidates
+        .into_iter()
+        .map(|(child, parents)| (child, owner(krate, &child, parents)))
+        .collect()
+}
+
+/// Of the items listing `child`, the module of its canonical path, otherwise the innermost one whose span
+/// contains the span of `child`, otherwise the one with the lowest id.
+fn owner(krate: &Crate, child: &Id, mut parents: Vec<Id>) -> Id {
+    if let [parent] = parents[..] {
+        return parent;
+    }
+    parents.sort();
+    if let Some(summary) = krate.paths.get(child) {
+        let path = &summary.path[..summary.path.len().saturating_sub(1)];
+        let owner = parents.iter().find(|parent| {
+            krate
+                .paths
+                .get(parent)
+                .is_some_and(|parent| parent.path == path)
+        });
+        if let Some(owner) = owner {
+            return *owner;
+        }
+    }
+    if let Some(span) = krate.index.get(child).and_then(|child| child.span.as_ref()) {
+        let owner = parents
+            .iter()
+            .filter_map(|parent| Some((parent, krate.index.get(parent)?.span.as_ref()?)))
+            .filter(|(_, parent)| {
+                parent.filename == span.filename
+                    && parent.begin <= span.begin
+                    && span.end <= parent.end
+            })
+            .min_by_key(|(_, parent)| Reverse(parent.begin));
+        if let Some((owner, _)) = owner {
+            return *owner;
+        }
+    }
+    parents[0]
+}
+
+/// The module outside of which `item` is not visible, or `None` if it is visible everywhere.
+pub(crate) fn scope<'a>(krate: &'a Crate, item: &'a Item) -> Option<&'a Id> {
+    match &item.visibility {
+        Visibility::Public => None,
+        Visibility::Crate => Some(&krate.root),
+        Visibility::Restricted { parent, .. } => Some(parent),
+        Visibility::Default => {
+            let Some(parent) = krate.parent_id(&item.id) else {
+                return Some(&krate.root);
+            };
+            let parent = &krate.index[parent];
+            match &parent.inner {
+                ItemEnum::Module(_) => Some(&parent.id),
+                // Variants, their fields and trait items are as visible as their parent.
+                ItemEnum::Enum(_) | ItemEnum::Variant(_) | ItemEnum::Trait(_) => {
+                    scope(krate, parent)
+                }
+                // Private fields are visible in the module defining the type.
+                ItemEnum::Struct(_) | ItemEnum::Union(_) => defining_module(krate, &parent.id),
+                ItemEnum::Impl(impl_) if impl_.trait_.is_some() => None,
+                // Private inherent methods are visible in the module defining the `Self` type.
+                ItemEnum::Impl(impl_) => match &impl_.for_ {
+                    Type::ResolvedPath(path) => defining_module(krate, &path.id),
+                    _ => Some(&krate.root),
+                },
+                _ => Some(&krate.root),
+            }
+        }
+    }
+}
+
+fn defining_module<'a>(krate: &'a Crate, id: &Id) -> Option<&'a Id> {
+    match krate.parent_id(id) {
+        Some(parent) if matches!(krate.index[parent].inner, ItemEnum::Module(_)) => Some(parent),
+        _ => Some(&krate.root),
+    }
+}
+
+/// Whether `module` is `ancestor` or one of its descendants.
+pub(crate) fn is_within(krate: &Crate, module: &Id, ancestor: &Id) -> bool {
+    let mut current = module;
+    loop {
+        if current == ancestor {
+            return true;
+        }
+        match krate.parent_id(current) {
+            Some(parent) => current = parent,
+            None => return false,
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use rustdoc_types::{Id, Item, ItemEnum, Visibility};
+
+use crate::{is_doc_hidden, AnyItem, Crate};
+
+struct Entry<'a> {
+    path: String,
+    item: &'a Item,
+    /// Modules entered on the way to this entry, used to break re-export cycles.
+    ancestors: Vec<Id>,
+}
+
+/// A depth-first walk over the module tree, created by [`Crate::walk`].
+///
+/// Yields `(path, item)` pairs, where `path` is the fully qualified path under which the item is reachable,
+/// e.g. `my_crate::foo::Bar`. The root module itself is yielded first.
+///
+/// ```no_run
+/// # let krate: crate_inspector::Crate = todo!();
+/// for (path, item) in krate.walk().follow_globs(false).skip_hidden(true) {
+///     println!("{path}: {:?}", item.id());
+/// }
+/// ```
+pub struct Walk<'a> {
+    krate: &'a Crate,
+    follow_reexports: bool,
+    follow_globs: bool,
+    skip_hidden: bool,
+    skip_stripped: bool,
+    stack: Vec<Entry<'a>>,
+}
+
+impl<'a> Walk<'a> {
+    pub(crate) fn new(krate: &'a Crate) -> Self {
+        let root = &krate.index[&krate.root];
+        let root = Entry {
+            path: root.name.clone().unwrap_or_default(),
+            item: root,
+            ancestors: vec![],
+        };
+        Self {
+            krate,
+            follow_reexports: true,
+            follow_globs: true,
+            skip_hidden: false,
+            skip_stripped: false,
+            stack: vec![root],
+        }
+    }
+
+    /// Whether to resolve `pub use foo::Bar;` to `Bar` itself (default: `true`).
+    /// If `false`, non-glob imports are skipped. Non-`pub` imports are never followed.
+    ///
+    /// Imports whose target is not in the index (e.g. items of external crates) are yielded as [`AnyItem::Use`].
+    pub fn follow_reexports(mut self, follow_reexports: bool) -> Self {
+        self.follow_reexports = follow_reexports;
+        self
+    }
+
+    /// Whether to expand `pub use foo::*;` into the public items of `foo` (default: `true`).
+    /// If `false`, glob imports are skipped. Non-`pub` glob imports are never expanded.
+    pub fn follow_globs(mut self, follow_globs: bool) -> Self {
+        self.follow_globs = follow_globs;
+        self
+    }
+
+    /// Whether to skip `#[doc(hidden)]` items and everything below them (default: `false`).
+    pub fn skip_hidden(mut self, skip_hidden: bool) -> Self {
+        self.skip_hidden = skip_hidden;
+        self
+    }
+
+    /// Whether to skip stripped modules and everything below them (default: `false`).
+    pub fn skip_stripped(mut self, skip_stripped: bool) -> Self {
+        self.skip_stripped = skip_stripped;
+        self
+    }
+
+    fn is_skipped(&self, item: &Item) -> bool {
+        if self.skip_hidden && is_doc_hidden(item) {
+            return true;
+        }
+        match &item.inner {
+            ItemEnum::Module(module) => self.skip_stripped && module.is_stripped,
+            _ => false,
+        }
+    }
+
+    fn expand(
+        &self,
+        module_items: &'a [Id],
+        path: &str,
+        ancestors: &[Id],
+        public_only: bool,
+        out: &mut Vec<Entry<'a>>,
+    ) {
+        for id in module_items {
+            let Some(item) = self.krate.index.get(id) else {
+                continue;
+            };
+            if self.is_skipped(item) || (public_only && item.visibility != Visibility::Public) {
+                continue;
+            }
+            match &item.inner {
+                ItemEnum::Use(import) if import.is_glob => {
+                    // A private `use foo::*;` only imports names into the module itself.
+                    if !self.follow_globs || item.visibility != Visibility::Public {
+                        continue;
+                    }
+                    let Some(target) = import.id.as_ref().and_then(|id| self.krate.index.get(id))
+                    else {
+                        continue;
+                    };
+                    if ancestors.contains(&target.id) || self.is_skipped(target) {
+                        continue;
+                    }
+                    match &target.inner {
+                        ItemEnum::Module(module) => {
+                            // Only public items (and public imports) are brought into scope by a glob import.
+                            let mut ancestors = ancestors.to_vec();
+                            ancestors.push(target.id);
+                            self.expand(&module.items, path, &ancestors, true, out);
+                        }
+                        ItemEnum::Enum(enum_) => {
+                            self.expand(&enum_.variants, path, ancestors, false, out);
+                        }
+                        _ => {}
+                    }
+                }
+                ItemEnum::Use(import) => {
+                    // Like a private glob import, a private `use foo::Bar;` does not re-export `Bar`.
+                    if !self.follow_reexports || item.visibility != Visibility::Public {
+                        continue;
+                    }
+                    let target = import
+                        .id
+                        .as_ref()
+                        .and_then(|id| self.krate.index.get(id))
+                        .unwrap_or(item);
+                    if ancestors.contains(&target.id) || self.is_skipped(target) {
+                        continue;
+                    }
+                    out.push(Entry {
+                        path: format!("{path}::{}", import.name),
+                        item: target,
+                        ancestors: ancestors.to_vec(),
+                    });
+                }
+                _ => {
+                    if ancestors.contains(&item.id) {
+                        continue;
+                    }
+                    let path = match &item.name {
+                        Some(name) => format!("{path}::{name}"),
+                        None => path.to_string(),
+                    };
+                    out.push(Entry {
+                        path,
+                        item,
+                        ancestors: ancestors.to_vec(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (String, AnyItem<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.stack.pop()?;
+        if let ItemEnum::Module(module) = &entry.item.inner {
+            let mut ancestors = entry.ancestors;
+            ancestors.push(entry.item.id);
+            let mut children = vec![];
+            self.expand(&module.items, &entry.path, &ancestors, false, &mut children);
+            self.stack.extend(children.into_iter().rev());
+        }
+        Some((entry.path, self.krate.any_item(entry.item)))
+    }
+}
@@ -0,0 +1,247 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::api::ApiDiff;
+use crate::progress::CancellationToken;
+use crate::scaffold::Source;
+use crate::{BuildCrateError, Crate, CrateBuilder};
+
+/// Rebuilds a crate whenever its sources change.
+///
+/// The sources of the local packages (the workspace members and path dependencies) are watched,
+/// ignoring target directories and hidden entries. Changes are debounced, so that saving several files triggers one build.
+/// For [`CrateBuilder::from_file`], the directory of the file is watched; other `from_*` builders and
+/// [`CrateBuilder::at_git_rev`] are built once, as their sources do not change.
+///
+/// ```no_run
+/// use crate_inspector::CrateBuilder;
+/// use crate_inspector::watch::{CrateWatcher, WatchEvent};
+///
+/// let (_watch, events) = CrateWatcher::default()
+///     .crate_builder(CrateBuilder::default().toolchain("nightly").silent(true))
+///     .diff(true)
+///     .watch_channel()
+///     .unwrap();
+/// for event in events {
+///     match event {
+///         WatchEvent::Built { krate, diff } => {
+///             println!("rebuilt {}", krate.name());
+///             for (path, _) in diff.iter().flat_map(|diff| diff.added()) {
+///                 println!("  added {path}");
+///             }
+///         }
+///         WatchEvent::Failed(err) => eprintln!("{err}"),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrateWatcher {
+    builder: CrateBuilder,
+    debounce: Duration,
+    diff: bool,
+}
+
+impl Default for CrateWatcher {
+    fn default() -> Self {
+        Self {
+            builder: CrateBuilder::default(),
+            debounce: Duration::from_millis(200),
+            diff: false,
+        }
+    }
+}
+
+/// The result of a build of a [`CrateWatcher`].
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// `diff` compares the public API with the previous successful build, if [`CrateWatcher::diff`] is enabled.
+    Built {
+        krate: Arc<Crate>,
+        diff: Option<ApiDiff>,
+    },
+    Failed(BuildCrateError),
+}
+
+impl CrateWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn crate_builder(mut self, builder: CrateBuilder) -> Self {
+        self.builder = builder;
+        self
+    }
+
+    /// How long to wait for further changes before rebuilding (200ms by default).
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Compute an [`ApiDiff`] against the previous successful build.
+    pub fn diff(mut self, diff: bool) -> Self {
+        self.diff = diff;
+        self
+    }
+
+    /// Starts watching. The crate is built right away, then on every change, and each result is passed to `callback`
+    /// on a background thread. Watching stops when the returned [`CrateWatch`] is dropped.
+    pub fn watch(
+        self,
+        mut callback: impl FnMut(WatchEvent) + Send + 'static,
+    ) -> Result<CrateWatch, BuildCrateError> {
+        let (roots, skip) = self.watched_paths()?;
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(std::io::Error::other)?;
+        for root in &roots {
+            watcher
+                .watch(root, RecursiveMode::Recursive)
+                .map_err(std::io::Error::other)?;
+        }
+
+        let token = self.builder.cancellation_token.clone().unwrap_or_default();
+        let builder = self.builder.cancellation_token(token.clone());
+        let (debounce, diff) = (self.debounce, self.diff);
+        let thread = std::thread::spawn({
+            let token = token.clone();
+            move || {
+                let mut previous = None::<Arc<Crate>>;
+                loop {
+                    let event = match builder.clone().build() {
+                        Ok(krate) => {
+                            let krate = Arc::new(krate);
+                            let diff = previous
+                                .as_ref()
+                                .filter(|_| diff)
+                                .map(|previous| ApiDiff::new(previous, &krate));
+                            previous = Some(Arc::clone(&krate));
+                            WatchEvent::Built { krate, diff }
+                        }
+                        Err(err) => WatchEvent::Failed(err),
+                    };
+                    if token.is_cancelled() {
+                        return;
+                    }
+                    callback(event);
+                    if !wait_for_change(&receiver, &roots, &skip, debounce) {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(CrateWatch {
+            watcher: Some(watcher),
+            token,
+            thread: Some(thread),
+        })
+    }
+
+    /// Like [`CrateWatcher::watch`], but sends the results to a channel.
+    pub fn watch_channel(self) -> Result<(CrateWatch, Receiver<WatchEvent>), BuildCrateError> {
+        let (sender, receiver) = mpsc::channel();
+        let watch = self.watch(move |event| {
+            let _ = sender.send(event);
+        })?;
+        Ok((watch, receiver))
+    }
+
+    /// The directories to watch, and the directories under them to ignore.
+    fn watched_paths(&self) -> Result<(Vec<PathBuf>, Vec<PathBuf>), BuildCrateError> {
+        if self.builder.git_rev.is_some() {
+            return Ok((vec![], vec![]));
+        }
+        match &self.builder.source {
+            Some(Source::File(path)) => {
+                let path = std::path::absolute(path)?;
+                let dir = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+                let skip = self.builder.target_dir.iter().cloned().collect();
+                Ok((vec![dir], skip))
+            }
+            Some(_) => Ok((vec![], vec![])),
+            None => {
+                let metadata = self.builder.metadata_command().exec()?;
+                Ok(self.builder.source_roots(&metadata))
+            }
+        }
+    }
+}
+
+/// Blocks until a source changes and no further changes happened for `debounce`.
+/// Returns `false` if the watcher was dropped.
+fn wait_for_change(
+    receiver: &Receiver<notify::Result<notify::Event>>,
+    roots: &[PathBuf],
+    skip: &[PathBuf],
+    debounce: Duration,
+) -> bool {
+    loop {
+        match receiver.recv() {
+            Ok(Ok(event)) if is_source_change(&event, roots, skip) => break,
+            Ok(_) => {}
+            Err(_) => return false,
+        }
+    }
+    loop {
+        match receiver.recv_timeout(debounce) {
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => return true,
+            Err(RecvTimeoutError::Disconnected) => return false,
+        }
+    }
+}
+
+/// Whether `event` modifies a file under one of the `roots`, excluding `skip`, hidden entries (e.g. `.git`)
+/// and `Cargo.lock`, which cargo itself writes during the build.
+fn is_source_change(event: &notify::Event, roots: &[PathBuf], skip: &[PathBuf]) -> bool {
+    if matches!(
+        event.kind,
+        EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_))
+    ) {
+        return false;
+    }
+    event.paths.iter().any(|path| {
+        let Some(relative) = roots.iter().find_map(|root| path.strip_prefix(root).ok()) else {
+            return false;
+        };
+        !skip.iter().any(|skip| path.starts_with(skip))
+            && path.file_name() != Some("Cargo.lock".as_ref())
+            && !relative
+                .components()
+                .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+    })
+}
+
+/// A running [`CrateWatcher`]. Dropping it stops watching and cancels a running build.
+pub struct CrateWatch {
+    watcher: Option<RecommendedWatcher>,
+    token: CancellationToken,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CrateWatch {
+    /// Stops watching and waits for the background thread to exit.
+    pub fn stop(self) {}
+}
+
+impl Drop for CrateWatch {
+    fn drop(&mut self) {
+        self.token.cancel();
+        // Dropping the watcher disconnects the channel the background thread waits on
+        self.watcher.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl std::fmt::Debug for CrateWatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrateWatch").finish_non_exhaustive()
+    }
+}
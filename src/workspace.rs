@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use cargo_metadata::TargetKind;
+
+use crate::{BuildCrateError, Crate, CrateBuilder};
+
+/// Builds every library member of a cargo workspace.
+///
+/// The options of the [`CrateBuilder`] given with [`WorkspaceBuilder::crate_builder`] (toolchain, features, ...)
+/// are applied to each member, except for the manifest path and package which are set per member.
+///
+/// ```no_run
+/// use crate_inspector::CrateBuilder;
+/// use crate_inspector::workspace::WorkspaceBuilder;
+///
+/// let workspace = WorkspaceBuilder::default()
+///     .manifest_path("Cargo.toml")
+///     .crate_builder(CrateBuilder::default().toolchain("nightly").silent(true))
+///     .build()
+///     .unwrap();
+/// for (name, krate) in workspace.crates() {
+///     println!("{name}: {} structs", krate.all_structs().count());
+/// }
+/// for (name, err) in workspace.errors() {
+///     eprintln!("{name}: {err}");
+/// }
+/// ```
+#[derive(Clone)]
+pub struct WorkspaceBuilder {
+    builder: CrateBuilder,
+    manifest_path: PathBuf,
+    exclude: Vec<String>,
+}
+
+impl Default for WorkspaceBuilder {
+    fn default() -> Self {
+        Self {
+            builder: CrateBuilder::default(),
+            manifest_path: PathBuf::from("Cargo.toml"),
+            exclude: vec![],
+        }
+    }
+}
+
+impl WorkspaceBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The manifest of the workspace root (default: `Cargo.toml`).
+    pub fn manifest_path(mut self, manifest_path: impl AsRef<Path>) -> Self {
+        self.manifest_path = manifest_path.as_ref().to_path_buf();
+        self
+    }
+
+    /// The builder used as a template for each member.
+    pub fn crate_builder(mut self, builder: CrateBuilder) -> Self {
+        self.builder = builder;
+        self
+    }
+
+    /// Skip the given packages.
+    pub fn exclude(mut self, packages: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        self.exclude
+            .extend(packages.into_iter().map(|p| p.as_ref().to_string()));
+        self
+    }
+
+    /// Reads the workspace with `cargo metadata` and builds each library member.
+    ///
+    /// Only failing to read the workspace is an error; build failures of individual members are collected into [`Workspace::errors`].
+    pub fn build(self) -> Result<Workspace, BuildCrateError> {
+        let metadata = self
+            .builder
+            .metadata_command()
+            .manifest_path(&self.manifest_path)
+            .no_deps()
+            .exec()?;
+        let mut workspace = Workspace::default();
+        for package in metadata.workspace_packages() {
+            if self.exclude.contains(&package.name) || !package.targets.iter().any(is_library) {
+                continue;
+            }
+            let result = self
+                .builder
+                .clone()
+                .manifest_path(&package.manifest_path)
+                .package(&package.name)
+                .build();
+            match result {
+                Ok(krate) => {
+                    workspace.crates.insert(package.name.clone(), krate);
+                }
+                Err(err) => {
+                    workspace.errors.insert(package.name.clone(), err);
+                }
+            }
+        }
+        Ok(workspace)
+    }
+}
+
+pub(crate) fn is_library(target: &cargo_metadata::Target) -> bool {
+    target.kind.iter().any(|kind| {
+        matches!(
+            kind,
+            TargetKind::Lib
+                | TargetKind::RLib
+                | TargetKind::DyLib
+                | TargetKind::CDyLib
+                | TargetKind::StaticLib
+                | TargetKind::ProcMacro
+        )
+    })
+}
+
+/// The result of [`WorkspaceBuilder::build`]: the crates of the members that built successfully,
+/// and the errors of the others, keyed by package name.
+#[derive(Debug, Default)]
+pub struct Workspace {
+    crates: BTreeMap<String, Crate>,
+    errors: BTreeMap<String, BuildCrateError>,
+}
+
+impl Workspace {
+    /// Get the crate of a member by its package name.
+    pub fn get(&self, package: &str) -> Option<&Crate> {
+        self.crates.get(package)
+    }
+
+    pub fn crates(&self) -> impl Iterator<Item = (&str, &Crate)> {
+        self.crates
+            .iter()
+            .map(|(name, krate)| (name.as_str(), krate))
+    }
+
+    pub fn into_crates(self) -> BTreeMap<String, Crate> {
+        self.crates
+    }
+
+    /// Get the build error of a member by its package name.
+    pub fn error(&self, package: &str) -> Option<&BuildCrateError> {
+        self.errors.get(package)
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &BuildCrateError)> {
+        self.errors.iter().map(|(name, err)| (name.as_str(), err))
+    }
+
+    /// Whether all members were built successfully.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
@@ -0,0 +1,243 @@
+mod common;
+
+#[test]
+fn test_build_errors() {
+    use std::error::Error;
+
+    use crate_inspector::{BuildCrateError, CrateBuilder};
+
+    use common::{scratch_dir, TestBuilder};
+
+    let dir = scratch_dir(
+        "broken",
+        &[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"broken\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+            ),
+            ("src/lib.rs", "pub fn f() -> Missing {\n    todo!()\n}\n"),
+        ],
+    );
+
+    let builder = CrateBuilder::default().for_tests();
+    let err = builder
+        .clone()
+        .manifest_path(dir.join("Cargo.toml"))
+        .build()
+        .unwrap_err();
+    let BuildCrateError::CompileFailed { diagnostics, .. } = &err else {
+        panic!("{err:?}");
+    };
+    assert!(!diagnostics.is_empty());
+    let error = err.errors().next().unwrap();
+    assert_eq!(error.code.as_ref().unwrap().code, "E0425");
+    assert!(error
+        .spans
+        .iter()
+        .any(|span| span.file_name == "src/lib.rs"));
+    assert!(err
+        .to_string()
+        .starts_with("failed to compile: cannot find type `Missing`"));
+
+    // Failing to write the diagnostics stops the build, after cargo has exited
+    struct Closed;
+    impl std::io::Write for Closed {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("closed"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let err = builder
+        .clone()
+        .manifest_path(dir.join("Cargo.toml"))
+        .build_with_captured_output(std::io::sink(), Closed)
+        .unwrap_err();
+    assert!(matches!(err, BuildCrateError::Io(_)), "{err:?}");
+
+    let err = builder
+        .clone()
+        .manifest_path(dir.join("Missing.toml"))
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, BuildCrateError::ManifestNotFound(_)));
+
+    let err = builder
+        .toolchain("no-such-toolchain")
+        .manifest_path(dir.join("Cargo.toml"))
+        .build()
+        .unwrap_err();
+    assert!(
+        matches!(&err, BuildCrateError::ToolchainNotFound { toolchain: Some(toolchain), .. } if toolchain == "no-such-toolchain"),
+        "{err:?}"
+    );
+    assert!(err.source().is_none());
+}
+
+#[test]
+fn test_pass_through_options() {
+    use crate_inspector::{BuildCrateError, CrateBuilder};
+
+    use common::TestBuilder;
+
+    let code = r#"
+#[cfg(docsrs)]
+pub struct DocsOnly;
+
+#[cfg(extra)]
+pub struct Extra;
+
+#[doc(hidden)]
+pub struct Hidden;
+
+pub const ENV: &str = env!("CRATE_INSPECTOR_TEST");
+"#;
+    let builder = CrateBuilder::from_source(code)
+        .for_tests()
+        .env("CRATE_INSPECTOR_TEST", "set")
+        .offline(true);
+    let krate = builder.clone().build().unwrap();
+    let names = |krate: &crate_inspector::Crate| {
+        let mut names = krate
+            .structs()
+            .map(|strc| strc.name().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+    assert!(names(&krate).is_empty());
+
+    let krate = builder
+        .clone()
+        .cfg("docsrs")
+        .rustdoc_flags(["--cfg", "extra"])
+        .document_hidden_items(true)
+        .build()
+        .unwrap();
+    assert_eq!(names(&krate), vec!["DocsOnly", "Extra", "Hidden"]);
+
+    let err = builder
+        .cargo_path("/nonexistent/cargo")
+        .build()
+        .unwrap_err();
+    assert!(
+        matches!(err, BuildCrateError::ToolchainNotFound { .. }),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn test_progress() {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use crate_inspector::progress::{BuildEvent, CancellationToken};
+    use crate_inspector::{BuildCrateError, CrateBuilder};
+
+    use common::{scratch_dir, write_files, TestBuilder};
+
+    let dir = scratch_dir(
+        "progress",
+        &[
+            (
+                "app/Cargo.toml",
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\ndep = { path = \"../dep\" }\n\n[workspace]\n",
+            ),
+            ("app/src/lib.rs", "pub use dep::Dep;\n"),
+            (
+                "dep/Cargo.toml",
+                "[package]\nname = \"dep\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("dep/src/lib.rs", "pub struct Dep;\n"),
+        ],
+    );
+    let builder = CrateBuilder::default()
+        .for_tests()
+        .manifest_path(dir.join("app").join("Cargo.toml"));
+
+    let events = Arc::new(Mutex::new(vec![]));
+    let krate = builder
+        .clone()
+        .on_progress({
+            let events = Arc::clone(&events);
+            move |event| events.lock().unwrap().push(event.clone())
+        })
+        .build()
+        .unwrap();
+    assert_eq!(krate.name(), "app");
+    let events = events.lock().unwrap();
+    assert_eq!(
+        events.first(),
+        Some(&BuildEvent::Started { total: Some(1) })
+    );
+    assert!(events.contains(&BuildEvent::Compiled {
+        name: "dep".to_string(),
+        done: 1,
+        total: Some(1)
+    }));
+    assert!(events.contains(&BuildEvent::Documenting {
+        name: "app".to_string()
+    }));
+    assert!(matches!(
+        &events[events.len() - 2..],
+        [BuildEvent::Parsing { .. }, BuildEvent::Finished]
+    ));
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let err = builder
+        .clone()
+        .cancellation_token(token)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, BuildCrateError::Cancelled), "{err:?}");
+
+    let err = builder
+        .clone()
+        .target_dir(dir.join("timeout-target"))
+        .timeout(Duration::from_millis(1))
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, BuildCrateError::TimedOut(_)), "{err:?}");
+
+    // The build script outlives cargo unless the whole process group is killed
+    let slow = dir.join("slow");
+    let pid_file = slow.join("build-script.pid");
+    write_files(
+        &slow,
+        &[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"slow\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+            ),
+            (
+                "build.rs",
+                &format!(
+                    "fn main() {{\n    std::fs::write({pid_file:?}, std::process::id().to_string()).unwrap();\n    std::thread::sleep(std::time::Duration::from_secs(120));\n}}\n"
+                ),
+            ),
+            ("src/lib.rs", "pub struct Slow;\n"),
+        ],
+    );
+    let started = std::time::Instant::now();
+    let err = CrateBuilder::default()
+        .for_tests()
+        .manifest_path(slow.join("Cargo.toml"))
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, BuildCrateError::TimedOut(_)), "{err:?}");
+    assert!(started.elapsed() < Duration::from_secs(60));
+    #[cfg(target_os = "linux")]
+    {
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let proc_dir = std::path::Path::new("/proc").join(pid.trim());
+        let killed = (0..100).any(|_| {
+            std::thread::sleep(Duration::from_millis(50));
+            std::fs::read_to_string(proc_dir.join("stat"))
+                .map_or(true, |stat| stat.contains(") Z "))
+        });
+        assert!(killed, "the build script is still running");
+    }
+}
@@ -0,0 +1,70 @@
+mod common;
+
+#[test]
+fn test_cache() {
+    use crate_inspector::CrateBuilder;
+
+    use common::{scratch_dir, write_files, TestBuilder};
+
+    let dir = scratch_dir(
+        "cached",
+        &[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"cached\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[features]\nextra = []\n\n[workspace]\n",
+            ),
+            ("src/lib.rs", "pub struct A;\n"),
+        ],
+    );
+    let cache_dir = dir.join("cache");
+
+    let builder = CrateBuilder::default()
+        .for_tests()
+        .manifest_path(dir.join("Cargo.toml"))
+        .cache_dir(&cache_dir);
+    let fingerprint = builder.fingerprint().unwrap();
+    assert_eq!(builder.fingerprint().unwrap(), fingerprint);
+    assert_ne!(
+        builder.clone().features(["extra"]).fingerprint().unwrap(),
+        fingerprint
+    );
+    write_files(
+        &dir,
+        &[(
+            ".cargo/config.toml",
+            "[build]\nrustdocflags = [\"--cfg\", \"docsrs\"]\n",
+        )],
+    );
+    assert_ne!(builder.fingerprint().unwrap(), fingerprint);
+    std::fs::remove_dir_all(dir.join(".cargo")).unwrap();
+
+    let krate = builder.clone().build().unwrap();
+    assert!(cache_dir.join(format!("{fingerprint}.json")).is_file());
+    // Remove the build output: the cached JSON must be used
+    std::fs::remove_dir_all(dir.join("target")).unwrap();
+    assert_eq!(builder.clone().build().unwrap(), krate);
+    assert!(!dir.join("target").exists());
+
+    std::fs::write(dir.join("src/lib.rs"), "pub struct A;\npub struct B;\n").unwrap();
+    assert_ne!(builder.fingerprint().unwrap(), fingerprint);
+    assert_eq!(builder.build().unwrap().structs().count(), 2);
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 2);
+
+    // The scaffold of `from_file` refers to the file outside of it: its directory is fingerprinted
+    let file_dir = dir.join("file");
+    write_files(
+        &file_dir,
+        &[
+            ("single.rs", "pub mod inner;\n"),
+            ("inner.rs", "pub struct A;\n"),
+        ],
+    );
+    let builder = CrateBuilder::from_file(file_dir.join("single.rs"))
+        .for_tests()
+        .cache_dir(&cache_dir);
+    let count_structs = |krate: crate_inspector::Crate| krate.all_structs().count();
+    assert_eq!(count_structs(builder.clone().build().unwrap()), 1);
+    std::fs::write(file_dir.join("inner.rs"), "pub struct A;\npub struct B;\n").unwrap();
+    assert_eq!(count_structs(builder.build().unwrap()), 2);
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 4);
+}
@@ -0,0 +1,42 @@
+//! Helpers shared by the integration tests. Each test crate uses only some of them.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate_inspector::{Crate, CrateBuilder};
+
+pub trait TestBuilder {
+    /// Build with the nightly toolchain, which rustdoc JSON requires, and without cargo's output.
+    fn for_tests(self) -> Self;
+}
+
+impl TestBuilder for CrateBuilder {
+    fn for_tests(self) -> Self {
+        self.toolchain("nightly").silent(true)
+    }
+}
+
+/// Build `code` as the `lib.rs` of a crate named `snippet`.
+pub fn build_source(code: &str) -> Crate {
+    CrateBuilder::from_source(code).for_tests().build().unwrap()
+}
+
+/// An empty directory `name` under the scratch directory of the tests, with `files` (paths relative to it and their
+/// contents) written into it.
+pub fn scratch_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    write_files(&dir, files);
+    dir
+}
+
+/// Write `files` (paths relative to `dir` and their contents), creating their directories.
+pub fn write_files(dir: &Path, files: &[(&str, &str)]) {
+    for (path, content) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
@@ -0,0 +1,164 @@
+mod common;
+
+#[test]
+fn test_format_version() {
+    use crate_inspector::compat::{self, NewerVersions, FORMAT_VERSION};
+    use crate_inspector::lazy::LazyCrate;
+    use crate_inspector::{BuildCrateError, Crate};
+    use rustdoc_types::{Attribute, AttributeRepr, ReprKind};
+    use serde_json::{json, Value};
+
+    use common::build_source;
+
+    let krate = build_source("pub struct Circle;\n");
+    let circle = krate.get_item_by_path("snippet::Circle").unwrap().id;
+    let mut current = serde_json::to_value(&*krate).unwrap();
+    current["format_version"] = FORMAT_VERSION.into();
+    // Before v54, attributes were strings
+    let with_string_attrs = |version: u32, attrs: Value| {
+        let mut json = current.clone();
+        json["format_version"] = version.into();
+        for item in json["index"].as_object_mut().unwrap().values_mut() {
+            let attrs = item["attrs"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(|attr| match attr {
+                    Value::String(s) => Some(format!("#[{s}]")),
+                    Value::Object(map) => map["other"].as_str().map(String::from),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            item["attrs"] = attrs.into();
+        }
+        json["index"][circle.0.to_string()]["attrs"] = attrs;
+        json
+    };
+
+    // Format version 43 had no target
+    let mut json = with_string_attrs(
+        43,
+        json!([
+            "#[repr(C, align(8))]",
+            "#[must_use = \"walk it\"]",
+            "#[doc(hidden)]"
+        ]),
+    );
+    json.as_object_mut().unwrap().remove("target");
+    let bytes = serde_json::to_vec(&json).unwrap();
+    assert_eq!(compat::format_version(&bytes).unwrap(), 43);
+
+    let upgraded = Crate::from_slice(&bytes).unwrap();
+    assert_eq!(upgraded.format_version, FORMAT_VERSION);
+    assert_eq!(upgraded.index.len(), krate.index.len());
+    assert_eq!(
+        upgraded.index[&circle].attrs,
+        vec![
+            Attribute::Repr(AttributeRepr {
+                kind: ReprKind::C,
+                align: Some(8),
+                packed: None,
+                int: None,
+            }),
+            Attribute::MustUse {
+                reason: Some("walk it".to_string()),
+            },
+            Attribute::Other("#[doc(hidden)]".to_string()),
+        ]
+    );
+
+    // v48..=v52 printed some attributes in their parsed form
+    let json = with_string_attrs(
+        52,
+        json!([
+            "#[attr = MustUse {reason: \"walk it\"}]",
+            "#[attr = MustUse]",
+            "#[attr = Inline(Hint)]",
+            "#[macro_use]"
+        ]),
+    );
+    let upgraded = Crate::from_slice(&serde_json::to_vec(&json).unwrap()).unwrap();
+    assert_eq!(
+        upgraded.index[&circle].attrs,
+        vec![
+            Attribute::MustUse {
+                reason: Some("walk it".to_string()),
+            },
+            Attribute::MustUse { reason: None },
+            Attribute::Other("#[attr = Inline(Hint)]".to_string()),
+            Attribute::Other("#[macro_use]".to_string()),
+        ]
+    );
+
+    // `Attribute::MacroUse` of v55 is not in `rustdoc-types` 0.55.0
+    let mut json = current.clone();
+    json["index"][circle.0.to_string()]["attrs"] = json!(["macro_use", "non_exhaustive"]);
+    let bytes = serde_json::to_vec(&json).unwrap();
+    let expected = vec![
+        Attribute::Other("#[macro_use]".to_string()),
+        Attribute::NonExhaustive,
+    ];
+    assert_eq!(
+        Crate::from_slice(&bytes).unwrap().index[&circle].attrs,
+        expected
+    );
+    let lazy = LazyCrate::from_slice(&bytes).unwrap();
+    assert_eq!(lazy.get(&circle).unwrap().unwrap().attrs, expected);
+
+    // Newer versions are loaded if they deserialize, unless they are rejected
+    let mut json = current.clone();
+    json["format_version"] = (FORMAT_VERSION + 1).into();
+    let bytes = serde_json::to_vec(&json).unwrap();
+    assert_eq!(
+        Crate::from_slice(&bytes).unwrap().index.len(),
+        krate.index.len()
+    );
+    assert!(LazyCrate::from_slice(&bytes).is_ok());
+    for result in [
+        Crate::from_slice_with(&bytes, NewerVersions::Reject).map(|_| ()),
+        LazyCrate::from_slice_with(&bytes, NewerVersions::Reject).map(|_| ()),
+    ] {
+        match result {
+            Err(BuildCrateError::FormatVersion {
+                expected,
+                actual,
+                source: None,
+            }) => {
+                assert_eq!(expected, FORMAT_VERSION);
+                assert_eq!(actual, FORMAT_VERSION + 1);
+            }
+            other => panic!("{other:?}"),
+        }
+    }
+    let mut broken = json.clone();
+    broken["index"] = json!([]);
+    let bytes = serde_json::to_vec(&broken).unwrap();
+    for result in [
+        Crate::from_slice(&bytes).map(|_| ()),
+        LazyCrate::from_slice(&bytes).map(|_| ()),
+    ] {
+        match result {
+            Err(BuildCrateError::FormatVersion {
+                actual,
+                source: Some(_),
+                ..
+            }) => assert_eq!(actual, FORMAT_VERSION + 1),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    json["format_version"] = 1.into();
+    json.as_object_mut().unwrap().remove("index");
+    match Crate::from_slice(&serde_json::to_vec(&json).unwrap()) {
+        Err(
+            err @ BuildCrateError::FormatVersion {
+                expected, actual, ..
+            },
+        ) => {
+            assert_eq!(expected, FORMAT_VERSION);
+            assert_eq!(actual, 1);
+            assert!(std::error::Error::source(&err).is_some());
+        }
+        other => panic!("{other:?}"),
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+#[test]
+fn test_feature_matrix() {
+    use crate_inspector::features::{FeatureMatrixBuilder, FeatureSet};
+    use crate_inspector::CrateBuilder;
+
+    use common::{scratch_dir, TestBuilder};
+
+    let dir = scratch_dir(
+        "features",
+        &[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"features\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[features]\ndefault = [\"a\"]\na = []\nb = []\n\n[workspace]\n",
+            ),
+            (
+                "src/lib.rs",
+                r#"
+pub struct Always;
+
+#[cfg(feature = "a")]
+pub struct A;
+
+#[cfg(feature = "b")]
+pub fn b() {}
+
+impl Always {
+    #[cfg(feature = "b")]
+    pub fn method_b(&self) {}
+}
+"#,
+            ),
+        ],
+    );
+
+    let matrix = FeatureMatrixBuilder::default()
+        .crate_builder(
+            CrateBuilder::default()
+                .for_tests()
+                .manifest_path(dir.join("Cargo.toml")),
+        )
+        .build()
+        .unwrap();
+    let only = |feature: &str| FeatureSet::Only(feature.to_string());
+    assert_eq!(
+        matrix.feature_sets().cloned().collect::<Vec<_>>(),
+        vec![
+            FeatureSet::None,
+            FeatureSet::Default,
+            FeatureSet::All,
+            only("a"),
+            only("b")
+        ]
+    );
+    assert_eq!(matrix.errors().count(), 0);
+
+    let sets = |path: &str| {
+        matrix
+            .get_item(path)
+            .unwrap()
+            .feature_sets()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    assert!(matrix
+        .get_item("features::Always")
+        .unwrap()
+        .is_always_exposed());
+    assert_eq!(
+        sets("features::A"),
+        vec![FeatureSet::Default, FeatureSet::All, only("a")]
+    );
+    assert_eq!(sets("features::b"), vec![FeatureSet::All, only("b")]);
+    assert_eq!(
+        sets("features::Always::method_b"),
+        vec![FeatureSet::All, only("b")]
+    );
+    assert_eq!(matrix.gated_items().count(), 3);
+    assert!(matrix
+        .get_item("features::A")
+        .unwrap()
+        .item_in(&FeatureSet::None)
+        .is_none());
+}
@@ -0,0 +1,47 @@
+mod common;
+
+#[test]
+fn test_fingerprints() {
+    use common::build_source;
+
+    let old = build_source(
+        "pub struct Changed {\n    pub x: u8,\n}\n\npub struct Documented;\n\npub enum Exhaustive {\n    A,\n}\n\npub fn unchanged(x: Changed) -> u8 {\n    x.x\n}\n\npub fn removed() {}\n",
+    );
+    let new = build_source(
+        "pub fn added() {}\n\npub struct Changed {\n    pub x: u16,\n}\n\n/// Docs.\npub struct Documented;\n\n#[non_exhaustive]\npub enum Exhaustive {\n    A,\n}\n\npub fn unchanged(x: Changed) -> u8 {\n    x.x as u8\n}\n",
+    );
+
+    let changed = |docs: bool| {
+        let old = old.fingerprinter().docs(docs).build();
+        let new = new.fingerprinter().docs(docs).build();
+        let mut changed = new
+            .changed_since(&old)
+            .map(|stable_id| stable_id.path().join("::"))
+            .collect::<Vec<_>>();
+        changed.sort();
+        let removed = new
+            .removed_since(&old)
+            .map(|stable_id| stable_id.path().join("::"))
+            .collect::<Vec<_>>();
+        assert_eq!(removed, ["removed"]);
+        changed
+    };
+    assert_eq!(
+        changed(false),
+        ["Changed", "Changed::x", "Exhaustive", "added"]
+    );
+    assert_eq!(
+        changed(true),
+        ["Changed", "Changed::x", "Documented", "Exhaustive", "added"]
+    );
+
+    let fingerprints = old.fingerprinter().build();
+    let unchanged = old.get_item_by_path("snippet::unchanged").unwrap();
+    let stable_id = old.stable_id(&unchanged.id).unwrap();
+    assert_eq!(
+        fingerprints.get(&stable_id),
+        old.fingerprinter().fingerprint(&unchanged.id).as_deref()
+    );
+    assert_eq!(fingerprints.get(&stable_id).unwrap().len(), 64);
+    assert!(fingerprints.len() > 5);
+}
@@ -0,0 +1,73 @@
+#[test]
+fn test_fixture() {
+    use crate_inspector::fixture::CrateFixture;
+    use crate_inspector::{AnyItem, CrateItem, FunctionItem};
+    use rustdoc_types::Type;
+
+    let u8_ = || Type::Primitive("u8".to_string());
+    let mut fixture = CrateFixture::new("fixture").crate_version("0.1.0");
+    let shapes = fixture.module(fixture.root(), "shapes");
+    let point = fixture.struct_(shapes, "Point", [("x", u8_()), ("y", u8_())]);
+    let area = fixture.trait_(shapes, "Area");
+    fixture.trait_method(
+        area,
+        "area",
+        [("self", Type::Generic("Self".to_string()))],
+        Some(u8_()),
+    );
+    let inherent = fixture.impl_(point, None);
+    let new = fixture.method(
+        inherent,
+        "new",
+        [("x", u8_()), ("y", u8_())],
+        Some(fixture.resolved(point)),
+    );
+    let trait_impl = fixture.impl_(point, Some(area));
+    fixture.method(
+        trait_impl,
+        "area",
+        [("self", Type::Generic("Self".to_string()))],
+        Some(u8_()),
+    );
+    fixture.enum_(shapes, "Kind", ["Round", "Square"]);
+    fixture.function(fixture.root(), "origin", [], Some(fixture.resolved(point)));
+    fixture.use_(fixture.root(), point);
+    let krate = fixture.build();
+
+    assert_eq!(krate.name(), "fixture");
+    assert_eq!(krate.crate_version.as_deref(), Some("0.1.0"));
+    assert_eq!(krate.sub_modules().count(), 1);
+    let shapes = krate.get_module("shapes").unwrap();
+    let point = shapes.get_struct("Point").unwrap();
+    assert_eq!(point.module().unwrap().name(), "shapes");
+    assert_eq!(
+        point
+            .fields()
+            .unwrap()
+            .map(|f| f.name().to_string())
+            .collect::<Vec<_>>(),
+        ["x", "y"]
+    );
+    assert_eq!(point.impls().count(), 2);
+    assert_eq!(point.trait_impls().count(), 1);
+    assert_eq!(shapes.get_trait("Area").unwrap().impls().count(), 1);
+    assert_eq!(shapes.get_enum("Kind").unwrap().variants().count(), 2);
+    let new = krate.downcast::<FunctionItem>(&krate.index[&new]).unwrap();
+    assert!(!new.is_method());
+    assert!(new.is_associated());
+    assert_eq!(new.inputs().count(), 2);
+    assert_eq!(krate.get_function("origin").unwrap().inputs().count(), 0);
+    assert!(krate.get_item_by_path("fixture::shapes::Point").is_some());
+    assert!(matches!(
+        krate
+            .uses()
+            .next()
+            .unwrap()
+            .target()
+            .map(|item| krate.any_item(item)),
+        Some(AnyItem::Struct(_))
+    ));
+    let paths = krate.walk().map(|(path, _)| path).collect::<Vec<_>>();
+    assert!(paths.contains(&"fixture::shapes::Kind".to_string()));
+    assert!(point.is_reachable());
+}
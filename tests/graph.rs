@@ -0,0 +1,84 @@
+mod common;
+
+#[test]
+fn test_graph() {
+    use crate_inspector::graph::CrateGraph;
+    use crate_inspector::{AnyItem, CrateBuilder, CrateItem, UseItem};
+
+    use common::{scratch_dir, TestBuilder};
+
+    let dir = scratch_dir(
+        "graph",
+        &[
+        (
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"base\", \"dep\", \"app\"]\nresolver = \"2\"\n",
+        ),
+        (
+            "base/Cargo.toml",
+            "[package]\nname = \"base\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        ),
+        ("base/src/lib.rs", "pub mod inner {\n    pub struct Base;\n}\n"),
+        (
+            "dep/Cargo.toml",
+            "[package]\nname = \"dep\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nbase = { path = \"../base\" }\n",
+        ),
+        (
+            "dep/src/lib.rs",
+            "mod inner {\n    pub struct Target {\n        pub field: u8,\n    }\n}\npub use base::inner::Base;\npub use inner::Target;\n",
+        ),
+        (
+            "app/Cargo.toml",
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\ndep = { path = \"../dep\" }\n",
+        ),
+        (
+            "app/src/lib.rs",
+            "pub use dep::Target as Reexported;\npub fn make() -> dep::Target {\n    todo!()\n}\npub fn base() -> &'static [dep::Base] {\n    todo!()\n}\n",
+        ),
+        ],
+    );
+
+    let manifest_path = dir.join("Cargo.toml");
+    let builder = CrateBuilder::default().for_tests();
+    let mut graph = CrateGraph::new(
+        builder
+            .clone()
+            .manifest_path(&manifest_path)
+            .package("app")
+            .build()
+            .unwrap(),
+    );
+    let errors = graph.load_dependencies(builder, &manifest_path).unwrap();
+    assert!(errors.is_empty(), "{errors:?}");
+    assert_eq!(
+        graph.crates().map(|krate| krate.name()).collect::<Vec<_>>(),
+        ["app", "base", "dep"]
+    );
+
+    let make = graph.root().get_function("make").unwrap();
+    let output = graph.resolve_type(graph.root(), make.output().unwrap());
+    let Some(AnyItem::Struct(strc)) = output else {
+        panic!("expected struct, got {output:?}");
+    };
+    assert_eq!(strc.name(), "Target");
+    assert_eq!(strc.krate().name(), "dep");
+    assert!(strc.fields().unwrap().any(|field| field.name() == "field"));
+
+    // The root crate resolves external ids itself
+    let base = graph.root().get_function("base").unwrap();
+    let output = graph.root().resolve_type(base.output().unwrap());
+    let Some(AnyItem::Struct(strc)) = output else {
+        panic!("expected struct, got {output:?}");
+    };
+    assert_eq!(strc.name(), "Base");
+    assert_eq!(strc.krate().name(), "base");
+
+    let root = graph.into_root();
+    let import = root
+        .items()
+        .find_map(|item| root.downcast::<UseItem>(item))
+        .unwrap();
+    assert_eq!(import.target().unwrap().name.as_deref(), Some("Target"));
+    let make = root.get_function("make").unwrap();
+    assert!(root.resolve_type(make.output().unwrap()).is_some());
+}
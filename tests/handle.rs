@@ -0,0 +1,27 @@
+mod common;
+
+#[test]
+fn test_handle() {
+    use std::sync::Arc;
+
+    use crate_inspector::{CrateItem, StructItem};
+
+    use common::build_source;
+
+    let krate = build_source("pub struct Circle;\n\npub struct Square;\n");
+    let krate = Arc::new(krate);
+
+    let handles = krate
+        .structs()
+        .map(|strc| krate.handle(strc.item().id).unwrap())
+        .collect::<Vec<_>>();
+    let names = std::thread::spawn(move || {
+        handles
+            .iter()
+            .map(|handle| handle.downcast::<StructItem>().unwrap().name().to_string())
+            .collect::<Vec<_>>()
+    })
+    .join()
+    .unwrap();
+    assert!(names.contains(&"Circle".to_string()));
+}
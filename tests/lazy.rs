@@ -0,0 +1,30 @@
+mod common;
+
+#[test]
+fn test_lazy() {
+    use crate_inspector::lazy::LazyCrate;
+    use crate_inspector::{Crate, CrateBuilder};
+
+    use common::TestBuilder;
+
+    let builder =
+        CrateBuilder::from_source("pub mod inner {\n    pub struct Foo;\n}\n\npub fn foo() {}\n")
+            .for_tests();
+    let krate = builder.clone().build().unwrap();
+    let lazy = builder.build_lazy().unwrap();
+    assert_eq!(lazy.name().unwrap(), "snippet");
+    assert_eq!(lazy.len(), krate.index.len());
+    let foo = lazy.get_by_path("snippet::inner::Foo").unwrap().unwrap();
+    assert_eq!(foo, krate.get_item_by_path("snippet::inner::Foo").unwrap());
+    assert!(lazy.get_by_path("snippet::Missing").unwrap().is_none());
+    assert_eq!(*lazy.into_crate().unwrap(), *krate);
+
+    // Like the builder, `from_reader` and `from_slice` load JSON of the toolchain's format version
+    let json = serde_json::to_vec(&*krate).unwrap();
+    let from_reader = Crate::from_reader(std::io::Cursor::new(&json)).unwrap();
+    assert_eq!(*from_reader, *krate);
+    assert_eq!(
+        *LazyCrate::from_slice(&json).unwrap().into_crate().unwrap(),
+        *krate
+    );
+}
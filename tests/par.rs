@@ -0,0 +1,19 @@
+#![cfg(feature = "rayon")]
+
+mod common;
+
+#[test]
+fn test_par() {
+    use rayon::prelude::*;
+
+    use common::build_source;
+
+    let krate = build_source(
+        "pub struct A;\n\npub struct B;\n\nimpl A {\n    pub fn new() -> Self {\n        A\n    }\n}\n\npub mod inner {\n    pub struct C;\n}\n",
+    );
+
+    assert_eq!(krate.par_items().count(), krate.items().count());
+    assert_eq!(krate.par_structs().count(), 2);
+    assert_eq!(krate.par_all_structs().count(), 3);
+    assert_eq!(krate.par_all_impls().count(), krate.all_impls().count());
+}
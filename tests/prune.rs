@@ -0,0 +1,131 @@
+mod common;
+
+#[test]
+fn test_prune() {
+    use crate_inspector::{Crate, CrateBuilder};
+    use rustdoc_types::{ItemEnum, StructKind};
+
+    use common::TestBuilder;
+
+    let krate = CrateBuilder::from_source(
+        "pub mod a {\n    pub struct A {\n        pub x: u8,\n        y: u8,\n    }\n\n    pub(crate) fn private() {}\n\n    pub mod inner {\n        pub(in crate::a) struct Restricted;\n    }\n}\n\npub mod b {\n    /// Wraps [`crate::a::A`], see also [`crate::a::private`].\n    pub struct B(pub crate::a::A, pub crate::hidden::H);\n}\n\nmod hidden {\n    pub struct H;\n}\n\npub use hidden::H;\n",
+    )
+    .for_tests()
+    .document_private_items(true)
+    .build()
+    .unwrap();
+    assert!(krate.get_item_by_path("snippet::a::private").is_some());
+
+    let public = krate.prune().public_only(true).build().unwrap();
+    assert!(public.get_item_by_path("snippet::a::private").is_none());
+    assert!(public.get_item_by_path("snippet::b::B").is_some());
+    assert!(public.get_item_by_path("snippet::hidden::H").is_some());
+    let a = public.get_item_by_path("snippet::a::A").unwrap();
+    let ItemEnum::Struct(a) = &a.inner else {
+        panic!("A is a struct")
+    };
+    let StructKind::Plain {
+        fields,
+        has_stripped_fields,
+    } = &a.kind
+    else {
+        panic!("A has named fields")
+    };
+    assert_eq!(fields.len(), 1);
+    assert!(has_stripped_fields);
+    // ids are renumbered densely, and every referenced id resolves
+    let max = public
+        .index
+        .keys()
+        .chain(public.paths.keys())
+        .map(|id| id.0)
+        .max()
+        .unwrap();
+    assert!((max as usize) < public.index.len() + public.paths.len());
+    assert!(public.index.contains_key(&public.root));
+
+    let mut json = vec![];
+    public.write_json(&mut json).unwrap();
+    let reloaded = Crate::from_slice(&json).unwrap();
+    assert_eq!(*reloaded, *public);
+
+    let b = krate.prune().module("snippet::b").build().unwrap();
+    assert!(b.get_item_by_path("snippet::b::B").is_some());
+    assert!(b.get_item_by_path("snippet::a::A").is_none());
+    assert_eq!(b.sub_modules().count(), 1);
+    assert!(b
+        .paths
+        .values()
+        .any(|summary| summary.path == ["snippet", "a", "A"]));
+    assert!(krate.prune().module("snippet::missing").build().is_none());
+
+    let local = krate.prune().external_summaries(false).build().unwrap();
+    assert!(local.paths.values().all(|summary| summary.crate_id == 0));
+    assert!(local.external_crates.is_empty());
+
+    /// The item ids in rustdoc JSON: `id`s (of items, paths and re-exports), `pub(in ..)` parents, links and id lists.
+    fn collect_ids(json: &serde_json::Value, key: Option<&str>, ids: &mut Vec<u64>) {
+        match json {
+            serde_json::Value::Number(id) if matches!(key, Some("id" | "parent")) => {
+                ids.extend(id.as_u64())
+            }
+            serde_json::Value::Array(values) => {
+                let lists = [
+                    "items",
+                    "fields",
+                    "variants",
+                    "impls",
+                    "implementations",
+                    "tuple",
+                ];
+                for value in values {
+                    match value {
+                        serde_json::Value::Number(id)
+                            if key.is_some_and(|key| lists.contains(&key)) =>
+                        {
+                            ids.extend(id.as_u64())
+                        }
+                        value => collect_ids(value, None, ids),
+                    }
+                }
+            }
+            serde_json::Value::Object(object) => {
+                for (key, value) in object {
+                    match (key.as_str(), value) {
+                        ("links", serde_json::Value::Object(links)) => {
+                            ids.extend(links.values().filter_map(serde_json::Value::as_u64))
+                        }
+                        ("paths" | "external_crates", _) => {}
+                        (key, value) => collect_ids(value, Some(key), ids),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for pruned in [
+        krate.prune().build().unwrap(),
+        krate.prune().public_only(true).build().unwrap(),
+        krate.prune().module("snippet::b").build().unwrap(),
+        krate.prune().module("snippet::a::inner").build().unwrap(),
+        local,
+    ] {
+        let mut json = vec![];
+        pruned.write_json(&mut json).unwrap();
+        let reloaded = Crate::from_slice(&json).unwrap();
+        let mut referenced = vec![];
+        collect_ids(
+            &serde_json::from_slice(&json).unwrap(),
+            None,
+            &mut referenced,
+        );
+        assert!(!referenced.is_empty());
+        for id in referenced {
+            let id = rustdoc_types::Id(id as u32);
+            assert!(
+                reloaded.index.contains_key(&id) || reloaded.paths.contains_key(&id),
+                "{id:?} does not resolve"
+            );
+        }
+    }
+}
@@ -0,0 +1,88 @@
+mod common;
+
+#[test]
+fn test_public_api() {
+    use crate_inspector::fixture::CrateFixture;
+    use crate_inspector::{CrateBuilder, CrateItem, StructItem};
+    use rustdoc_types::{ItemEnum, Visibility};
+
+    use common::TestBuilder;
+
+    let krate = CrateBuilder::from_source(
+        "pub mod shapes {\n    pub struct Polygon {\n        points: Vec<u8>,\n        pub sides: u8,\n    }\n\n    struct Vertex;\n\n    pub(crate) fn helper() {}\n\n    pub mod inner {\n        pub(in crate::shapes) fn scoped() {}\n    }\n}\n\nmod private {\n    pub struct Unreachable;\n    pub struct Exported;\n\n    #[doc(hidden)]\n    pub struct Hidden;\n}\n\npub mod render {}\n\npub use private::{Exported, Hidden};\n",
+    )
+    .for_tests()
+    .document_private_items(true)
+    .document_hidden_items(true)
+    .build()
+    .unwrap();
+
+    let polygon = krate.all_structs().find(|s| s.name() == "Polygon").unwrap();
+    assert!(polygon.is_reachable());
+    let vertex = krate.all_structs().find(|s| s.name() == "Vertex").unwrap();
+    assert!(!vertex.is_public());
+    assert!(!vertex.is_reachable());
+    // `pub` but defined in a private module is not reachable, unless it is re-exported
+    let unreachable = krate
+        .all_structs()
+        .find(|s| s.name() == "Unreachable")
+        .unwrap();
+    assert!(unreachable.is_public());
+    assert!(!unreachable.is_reachable());
+    let exported = krate
+        .all_structs()
+        .find(|s| s.name() == "Exported")
+        .unwrap();
+    assert!(exported.is_reachable());
+    let hidden = krate.all_structs().find(|s| s.name() == "Hidden").unwrap();
+    assert!(!hidden.is_reachable());
+
+    let helper = krate
+        .all_functions()
+        .find(|f| f.name() == "helper")
+        .unwrap();
+    assert_eq!(helper.visibility(), &Visibility::Crate);
+    let render = krate.get_module("render").unwrap();
+    assert!(helper.is_visible_from(&render));
+    assert!(render.is_within(&krate.root_module()));
+    let scoped = krate
+        .all_functions()
+        .find(|f| f.name() == "scoped")
+        .unwrap();
+    assert_eq!(
+        scoped.visibility_scope().map(|m| m.name().to_string()),
+        Some("shapes".to_string())
+    );
+    assert!(!scoped.is_visible_from(&render));
+    // private field
+    let points = polygon
+        .fields()
+        .unwrap()
+        .find(|f| f.name() == "points")
+        .unwrap();
+    assert_eq!(
+        points.visibility_scope().map(|m| m.name().to_string()),
+        Some("shapes".to_string())
+    );
+    assert!(!points.is_visible_from(&render));
+    assert!(polygon
+        .fields()
+        .unwrap()
+        .any(|f| f.name() == "sides" && f.is_visible_from(&render)));
+
+    // An item listed by several modules belongs to the module of its canonical path
+    for _ in 0..8 {
+        let mut fixture = CrateFixture::new("fixture");
+        let modules = ["a", "b", "c", "d"].map(|name| fixture.module(fixture.root(), name));
+        let owned = fixture.struct_(modules[2], "Owned", []);
+        for module in [modules[0], modules[1], modules[3]] {
+            let ItemEnum::Module(module) = &mut fixture.item_mut(module).inner else {
+                unreachable!()
+            };
+            module.items.push(owned);
+        }
+        let krate = fixture.build();
+        let owned = krate.downcast::<StructItem>(&krate.index[&owned]).unwrap();
+        assert_eq!(owned.module().unwrap().name(), "c");
+    }
+}
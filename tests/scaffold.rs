@@ -0,0 +1,171 @@
+mod common;
+
+#[test]
+fn test_from_source() {
+    use crate_inspector::CrateBuilder;
+
+    use common::{build_source, scratch_dir, TestBuilder};
+
+    let krate = build_source("pub struct Foo;\n\npub fn foo() -> Foo {\n    Foo\n}\n");
+    assert_eq!(krate.name(), "snippet");
+    assert_eq!(krate.structs().next().unwrap().name(), "Foo");
+    assert_eq!(krate.functions().count(), 1);
+
+    let dir = scratch_dir(
+        "single-file",
+        &[
+            ("my-file.rs", "pub mod inner;\n"),
+            ("inner.rs", "pub enum Bar {}\n"),
+        ],
+    );
+    let krate = CrateBuilder::from_file(dir.join("my-file.rs"))
+        .for_tests()
+        .build()
+        .unwrap();
+    assert_eq!(krate.name(), "my_file");
+    assert_eq!(
+        krate
+            .get_item_by_path("my_file::inner::Bar")
+            .unwrap()
+            .name
+            .as_deref(),
+        Some("Bar")
+    );
+}
+
+#[test]
+fn test_crate_archive_and_vendor_dir() {
+    use crate_inspector::CrateBuilder;
+
+    use common::{scratch_dir, TestBuilder};
+
+    let dir = scratch_dir(
+        "archive",
+        &[
+        (
+            "archived/Cargo.toml",
+            "[package]\nname = \"archived\"\nversion = \"0.1.0\"\nedition = \"2021\"\ndescription = \"test\"\nlicense = \"MIT\"\n\n[workspace]\n",
+        ),
+        ("archived/src/lib.rs", "pub struct Archived;\n"),
+        (
+            "vendor/dep/Cargo.toml",
+            "[package]\nname = \"dep\"\nversion = '0.1.0' # not \"0.2.0\"\nedition = \"2021\"\n",
+        ),
+        ("vendor/dep/src/lib.rs", "pub struct Dep;\n"),
+        ("vendor/dep/.cargo-checksum.json", "{\"files\":{},\"package\":null}"),
+        (
+            "vendor/app-0.1.0/Cargo.toml",
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies.dep]\nversion = \"0.1\"\n\n[dev-dependencies.not-vendored]\nversion = \"1\"\n\n[target.'cfg(all())'.dev-dependencies]\nalso-not-vendored = { version = \"1\" }\n",
+        ),
+        ("vendor/app-0.1.0/src/lib.rs", "pub struct App(pub dep::Dep);\n"),
+        ("vendor/app-0.1.0/.cargo-checksum.json", "{\"files\":{},\"package\":null}"),
+        ],
+    );
+
+    let status = std::process::Command::new("cargo")
+        .args([
+            "package",
+            "--offline",
+            "--no-verify",
+            "--allow-dirty",
+            "--quiet",
+        ])
+        .current_dir(dir.join("archived"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let krate =
+        CrateBuilder::from_crate_archive(dir.join("archived/target/package/archived-0.1.0.crate"))
+            .for_tests()
+            .build()
+            .unwrap();
+    assert_eq!(krate.name(), "archived");
+    assert!(krate.get_struct("Archived").is_some());
+
+    let krate = CrateBuilder::from_vendor_dir("app", "0.1.0")
+        .vendor_dir(dir.join("vendor"))
+        .for_tests()
+        .build()
+        .unwrap();
+    assert_eq!(krate.name(), "app");
+    assert!(krate.get_struct("App").is_some());
+
+    let krate = CrateBuilder::from_vendor_dir("dep", "0.1.0")
+        .vendor_dir(dir.join("vendor"))
+        .for_tests()
+        .build()
+        .unwrap();
+    assert!(krate.get_struct("Dep").is_some());
+    assert!(CrateBuilder::from_vendor_dir("dep", "0.2.0")
+        .vendor_dir(dir.join("vendor"))
+        .for_tests()
+        .build()
+        .is_err());
+}
+
+#[test]
+fn test_at_git_rev() {
+    use crate_inspector::{BuildCrateError, CrateBuilder};
+
+    use common::{scratch_dir, TestBuilder};
+
+    let dir = scratch_dir(
+        "git-rev",
+        &[
+            (".gitignore", "target\n"),
+            (
+                "lib/Cargo.toml",
+                "[package]\nname = \"versioned\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+            ),
+            ("lib/src/lib.rs", "pub struct Old;\n"),
+        ],
+    );
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(&dir)
+            .stdout(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "--quiet"]);
+    git(&["add", "."]);
+    git(&["commit", "--quiet", "-m", "v1"]);
+    git(&["tag", "v1"]);
+    std::fs::write(
+        dir.join("lib").join("src").join("lib.rs"),
+        "pub struct New;\n",
+    )
+    .unwrap();
+    git(&["commit", "--quiet", "-am", "v2"]);
+
+    let builder = CrateBuilder::default()
+        .for_tests()
+        .manifest_path(dir.join("lib").join("Cargo.toml"));
+    let old = builder.clone().at_git_rev("v1").build().unwrap();
+    assert!(old.get_struct("Old").is_some());
+    assert!(old.get_struct("New").is_none());
+    let new = builder.clone().build().unwrap();
+    assert!(new.get_struct("New").is_some());
+
+    let worktrees = std::process::Command::new("git")
+        .args(["worktree", "list", "--porcelain"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let worktrees = String::from_utf8(worktrees.stdout).unwrap();
+    assert_eq!(worktrees.matches("worktree ").count(), 1, "{worktrees}");
+
+    let err = builder
+        .clone()
+        .at_git_rev("no-such-rev")
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, BuildCrateError::Git { .. }), "{err:?}");
+    // Not taken as an option of `git worktree add`
+    let err = builder.at_git_rev("--orphan").build().unwrap_err();
+    assert!(matches!(err, BuildCrateError::Git { .. }), "{err:?}");
+    assert!(!dir.join("--orphan").exists());
+}
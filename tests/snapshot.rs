@@ -0,0 +1,45 @@
+mod common;
+
+#[test]
+fn test_snapshot() {
+    use crate_inspector::snapshot::SnapshotHeader;
+    use crate_inspector::{BuildCrateError, Crate, CrateItem};
+
+    use common::{build_source, scratch_dir};
+
+    let dir = scratch_dir("snapshot", &[]);
+    let krate = build_source("/// Foo\npub struct Foo;\n\npub fn foo(_: Foo) {}\n");
+    let path = dir.join("snippet.snapshot");
+    krate.save_snapshot(&path, Some("abc")).unwrap();
+
+    let header = SnapshotHeader::read(&path).unwrap();
+    assert_eq!(header.fingerprint(), Some("abc"));
+    assert_eq!(
+        header.format_version(),
+        crate_inspector::compat::FORMAT_VERSION
+    );
+    let loaded = Crate::load_snapshot(&path).unwrap();
+    assert_eq!(*loaded, *krate);
+    assert_eq!(
+        loaded.get_struct("Foo").unwrap().item().docs.as_deref(),
+        Some("Foo")
+    );
+
+    // Only the snapshot is left in the directory
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    std::fs::write(&path, b"{}").unwrap();
+    let err = Crate::load_snapshot(&path).unwrap_err();
+    assert!(matches!(err, BuildCrateError::Io(_)), "{err:?}");
+
+    // A header whose fingerprint claims to be 2^62 bytes long
+    let mut corrupted = b"CRISNAP\0".to_vec();
+    corrupted.extend(crate_inspector::snapshot::SNAPSHOT_VERSION.to_le_bytes());
+    corrupted.extend([55, 1, 0xfd]);
+    corrupted.extend((1u64 << 62).to_le_bytes());
+    std::fs::write(&path, corrupted).unwrap();
+    let err = SnapshotHeader::read(&path).unwrap_err();
+    assert!(matches!(err, BuildCrateError::Io(_)), "{err:?}");
+    let err = Crate::load_snapshot(&path).unwrap_err();
+    assert!(matches!(err, BuildCrateError::Io(_)), "{err:?}");
+}
@@ -0,0 +1,92 @@
+mod common;
+
+#[test]
+fn test_stable_id() {
+    use crate_inspector::stable_id::StableId;
+    use crate_inspector::{Crate, CrateItem};
+    use rustdoc_types::{ItemEnum, ItemKind, StructKind};
+
+    use common::build_source;
+
+    let old = build_source(
+        "pub mod shapes {\n    #[derive(Clone)]\n    pub struct Point<T> {\n        pub x: T,\n    }\n\n    impl<T> Point<T> {\n        pub fn x(&self) -> &T {\n            &self.x\n        }\n    }\n\n    pub enum Kind {\n        Round,\n    }\n}\n\npub trait Area {\n    fn area(&self) -> u8;\n}\n\nimpl<T: Clone> Area for shapes::Point<T>\nwhere\n    T: Send,\n{\n    fn area(&self) -> u8 {\n        0\n    }\n}\n",
+    );
+    // new items shift the ids, and the impls are written differently, with other names for their parameters
+    let new = build_source(
+        "pub fn first() {}\n\npub struct Other;\n\npub mod shapes {\n    pub struct Unrelated;\n\n    #[derive(Clone)]\n    pub struct Point<T> {\n        pub x: T,\n        pub y: T,\n    }\n\n    impl<U> self::Point<U> {\n        pub fn x(&self) -> &U {\n            &self.x\n        }\n    }\n\n    pub enum Kind {\n        Square,\n        Round,\n    }\n}\n\npub trait Area {\n    fn area(&self) -> u8;\n}\n\nimpl<P: Clone> crate::Area for crate::shapes::Point<P>\nwhere\n    P: Send,\n{\n    fn area(&self) -> u8 {\n        0\n    }\n}\n",
+    );
+
+    let point = old.get_item_by_path("snippet::shapes::Point").unwrap();
+    let point_id = old.stable_id(&point.id).unwrap();
+    assert_eq!(point_id.krate(), "snippet");
+    assert_eq!(point_id.path(), ["shapes", "Point"]);
+    assert_eq!(point_id.kind(), ItemKind::Struct);
+    assert_eq!(point_id.to_string(), "Struct snippet::shapes::Point");
+    let ItemEnum::Struct(point) = &point.inner else {
+        panic!("Point is a struct")
+    };
+
+    let mut checked = 0;
+    for id in [
+        vec![point_id.clone()],
+        point
+            .impls
+            .iter()
+            .filter_map(|id| old.stable_id(id))
+            .collect(),
+    ]
+    .concat()
+    {
+        let old_item = old.by_stable_id(&id).unwrap();
+        assert_eq!(old.stable_id(&old_item.id).as_ref(), Some(&id));
+        // stable ids survive serialization and resolve in the other build
+        let json = serde_json::to_string(&id).unwrap();
+        let id = serde_json::from_str::<StableId>(&json).unwrap();
+        let new_item = new
+            .by_stable_id(&id)
+            .unwrap_or_else(|| panic!("{id} not found"));
+        assert_eq!(new.stable_id(&new_item.id).as_ref(), Some(&id));
+        checked += 1;
+    }
+    assert!(checked > 3);
+
+    let area = |krate: &Crate| {
+        let method = krate
+            .all_functions()
+            .find(|f| f.name() == "area" && f.has_body())
+            .unwrap();
+        krate.stable_id(&method.item().id).unwrap()
+    };
+    assert_eq!(area(&old), area(&new));
+    let area = area(&old);
+    assert_eq!(area.impl_().unwrap().trait_(), Some("snippet::Area"));
+    assert_eq!(area.impl_().unwrap().for_(), "snippet::shapes::Point<T0>");
+    // rustdoc moves the bounds of the parameters into the where clause
+    assert_eq!(area.impl_().unwrap().generics(), "<T0>");
+    assert_eq!(
+        area.impl_().unwrap().where_clause(),
+        "where T0: core::marker::Send + core::clone::Clone"
+    );
+    assert_eq!(
+        area.to_string(),
+        "Function snippet::<impl<T0> snippet::Area for snippet::shapes::Point<T0> where T0: core::marker::Send + core::clone::Clone>::area"
+    );
+
+    let round = old.get_item_by_path("snippet::shapes::Kind").unwrap();
+    let ItemEnum::Enum(kind) = &round.inner else {
+        panic!("Kind is an enum")
+    };
+    let round = old.stable_id(&kind.variants[0]).unwrap();
+    assert_eq!(round.path(), ["shapes", "Kind", "Round"]);
+    assert_eq!(
+        new.by_stable_id(&round).unwrap().name.as_deref(),
+        Some("Round")
+    );
+    let StructKind::Plain { fields, .. } = &point.kind else {
+        panic!("Point has named fields")
+    };
+    let x = old.stable_id(&fields[0]).unwrap();
+    assert_eq!(x.path(), ["shapes", "Point", "x"]);
+    assert_eq!(x.kind(), ItemKind::StructField);
+    assert_eq!(new.by_stable_id(&x).unwrap().name.as_deref(), Some("x"));
+}
@@ -0,0 +1,44 @@
+mod common;
+
+#[test]
+fn test_sysroot() {
+    use crate_inspector::compat::{NewerVersions, FORMAT_VERSION};
+    use crate_inspector::{BuildCrateError, Crate};
+
+    use common::{build_source, scratch_dir};
+
+    // A sysroot whose JSON is newer than `FORMAT_VERSION`, as with the component of a recent nightly
+    let krate = build_source("pub trait Iterator {}\n");
+    let mut json = serde_json::to_value(&*krate).unwrap();
+    json["format_version"] = (FORMAT_VERSION + 1).into();
+    let json = serde_json::to_string(&json).unwrap();
+    let sysroot = scratch_dir("sysroot", &[("share/doc/rust/json/snippet.json", &json)]);
+    let loaded = Crate::load_sysroot_from(&sysroot, "snippet", NewerVersions::Try).unwrap();
+    assert_eq!(loaded.format_version, FORMAT_VERSION + 1);
+    assert!(loaded
+        .all_traits()
+        .any(|trait_| trait_.name() == "Iterator"));
+    assert!(matches!(
+        Crate::load_sysroot_from(&sysroot, "snippet", NewerVersions::Reject),
+        Err(BuildCrateError::FormatVersion { source: None, .. })
+    ));
+    assert!(matches!(
+        Crate::load_sysroot_from(&sysroot, "core", NewerVersions::Try),
+        Err(BuildCrateError::MissingSysrootJson {
+            toolchain: None,
+            ..
+        })
+    ));
+
+    // The `rust-docs-json` component may not be installed
+    match Crate::load_sysroot_for("nightly", "core") {
+        Ok(core) => {
+            assert_eq!(core.name(), "core");
+            assert!(core.all_traits().any(|trait_| trait_.name() == "Iterator"));
+        }
+        Err(BuildCrateError::MissingSysrootJson { path, .. }) => {
+            assert!(path.ends_with("share/doc/rust/json/core.json"));
+        }
+        Err(err) => panic!("{err}"),
+    }
+}
@@ -0,0 +1,160 @@
+mod common;
+
+#[test]
+fn test_target_comparison() {
+    use crate_inspector::targets::{TargetComparison, TargetComparisonBuilder};
+    use crate_inspector::CrateBuilder;
+
+    use common::{build_source, TestBuilder};
+
+    // Stand-ins for the builds of a crate using `#[cfg(target_os = ...)]` etc.
+    let unix = build_source(
+        "pub fn common(fd: i32) {}\npub fn unix_only() {}\npub const WORD: usize = 8;\n",
+    );
+    let windows = build_source("pub fn common(handle: *mut u8) {}\npub const WORD: usize = 4;\n");
+    let comparison = TargetComparison::new([
+        ("x86_64-unknown-linux-gnu".to_string(), unix),
+        ("x86_64-pc-windows-msvc".to_string(), windows),
+    ]);
+    assert_eq!(
+        comparison
+            .partial_items()
+            .map(|item| item.path())
+            .collect::<Vec<_>>(),
+        vec!["snippet::unix_only"]
+    );
+    assert_eq!(
+        comparison
+            .get_item("snippet::unix_only")
+            .unwrap()
+            .targets()
+            .collect::<Vec<_>>(),
+        vec!["x86_64-unknown-linux-gnu"]
+    );
+    assert_eq!(
+        comparison
+            .differing_items()
+            .map(|item| item.path())
+            .collect::<Vec<_>>(),
+        vec!["snippet::WORD", "snippet::common"]
+    );
+    let signatures = comparison.get_item("snippet::WORD").unwrap().signatures();
+    assert_eq!(signatures[0].1, "usize = 8usize");
+    assert_eq!(signatures[1].1, "usize = 4usize");
+
+    let host = "x86_64-unknown-linux-gnu";
+    let comparison = TargetComparisonBuilder::default()
+        .crate_builder(CrateBuilder::from_source("pub struct Foo;").for_tests())
+        .targets([host])
+        .build();
+    assert_eq!(comparison.errors().count(), 0);
+    assert_eq!(comparison.targets().collect::<Vec<_>>(), vec![host]);
+    assert!(comparison
+        .get_item("snippet::Foo")
+        .unwrap()
+        .is_on_all_targets());
+
+    // Where clauses are part of the signatures
+    let comparison = TargetComparison::new([
+        (
+            "x86_64-unknown-linux-gnu".to_string(),
+            build_source(
+                "pub fn bounded<T>(t: T) where T: Clone {}\npub struct W<T>(T) where T: Send;\n",
+            ),
+        ),
+        (
+            "x86_64-pc-windows-msvc".to_string(),
+            build_source(
+                "pub fn bounded<T>(t: T) where T: Copy {}\npub struct W<T>(T) where T: Sync;\n",
+            ),
+        ),
+    ]);
+    assert_eq!(
+        comparison
+            .differing_items()
+            .map(|item| item.path())
+            .collect::<Vec<_>>(),
+        vec!["snippet::W", "snippet::bounded"]
+    );
+    let signatures = comparison.get_item("snippet::W").unwrap().signatures();
+    assert_eq!(signatures[0].1, "<T>(_) where T: Send");
+
+    // So are the shapes of structs and variants, supertraits, `unsafe` and the bounds of associated types
+    let comparison = TargetComparison::new([
+        (
+            "x86_64-unknown-linux-gnu".to_string(),
+            build_source("pub struct Unit;\npub enum E { V(u8) }\npub trait Super: Clone {}\npub unsafe trait Unsafe {}\npub trait Assoc { type Out: Clone; }\n"),
+        ),
+        (
+            "x86_64-pc-windows-msvc".to_string(),
+            build_source("pub struct Unit(pub u8);\npub enum E { V { x: u8 } }\npub trait Super: Copy {}\npub trait Unsafe {}\npub trait Assoc { type Out: Copy; }\n"),
+        ),
+    ]);
+    assert_eq!(
+        comparison
+            .differing_items()
+            .map(|item| item.path())
+            .collect::<Vec<_>>(),
+        vec![
+            "snippet::Assoc::Out",
+            "snippet::E::V",
+            "snippet::Super",
+            "snippet::Unit",
+            "snippet::Unsafe"
+        ]
+    );
+    let signatures = |path: &str| {
+        comparison
+            .get_item(path)
+            .unwrap()
+            .signatures()
+            .into_iter()
+            .map(|(_, signature)| signature)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(signatures("snippet::Unit"), ["", "(_)"]);
+    assert_eq!(signatures("snippet::E::V"), ["(_)", " { .. }"]);
+    assert_eq!(
+        signatures("snippet::Super"),
+        ["trait: Clone", "trait: Copy"]
+    );
+    assert_eq!(signatures("snippet::Unsafe"), ["unsafe trait", "trait"]);
+    assert_eq!(
+        signatures("snippet::Assoc::Out"),
+        [": Clone = ", ": Copy = "]
+    );
+
+    // Building for a second target needs its standard library
+    let other = "wasm32-unknown-unknown";
+    let libdir = std::process::Command::new("rustc")
+        .args(["+nightly", "--print", "target-libdir", "--target", other])
+        .output()
+        .unwrap();
+    let libdir = String::from_utf8(libdir.stdout).unwrap();
+    if !std::path::Path::new(libdir.trim()).is_dir() {
+        eprintln!("skipping the comparison with {other}: its standard library is not installed");
+        return;
+    }
+    let comparison = TargetComparisonBuilder::default()
+        .crate_builder(
+            CrateBuilder::from_source(
+                "#[cfg(target_arch = \"wasm32\")]\npub fn wasm_only() {}\npub fn common() {}\n",
+            )
+            .for_tests(),
+        )
+        .targets([host, other])
+        .build();
+    assert_eq!(comparison.errors().count(), 0);
+    assert_eq!(comparison.targets().collect::<Vec<_>>(), vec![host, other]);
+    assert!(comparison
+        .get_item("snippet::common")
+        .unwrap()
+        .is_on_all_targets());
+    assert_eq!(
+        comparison
+            .partial_items()
+            .map(|item| item.path())
+            .collect::<Vec<_>>(),
+        vec!["snippet::wasm_only"]
+    );
+}
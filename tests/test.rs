@@ -7,9 +7,9 @@ fn test() {
         .manifest_path("Cargo.toml");
     let krate = builder.build().unwrap();
 
-    assert_eq!(krate.sub_modules().count(), 1);
+    assert_eq!(krate.sub_modules().count(), 16);
     assert_eq!(krate.structs().count(), 17);
-    assert_eq!(krate.enums().count(), 1);
+    assert_eq!(krate.enums().count(), 3);
     assert_eq!(krate.functions().count(), 0);
     assert_eq!(krate.traits().count(), 3);
 }
@@ -0,0 +1,76 @@
+mod common;
+
+#[test]
+fn test_walk() {
+    use crate_inspector::fixture::CrateFixture;
+    use crate_inspector::{AnyItem, CrateBuilder};
+    use rustdoc_types::{ItemEnum, Visibility};
+
+    use common::TestBuilder;
+
+    let krate = CrateBuilder::from_source(
+        "pub mod shapes {\n    pub fn area() {}\n}\n\nmod private {\n    pub struct Circle;\n}\n\npub mod glob {\n    pub use crate::shapes::*;\n}\n\npub use private::Circle;\n",
+    )
+    .for_tests()
+    .document_private_items(true)
+    .build()
+    .unwrap();
+
+    let paths = krate.walk().map(|(path, _)| path).collect::<Vec<_>>();
+    assert_eq!(paths[0], "snippet");
+    assert!(paths.contains(&"snippet::Circle".to_string()));
+    assert!(paths.contains(&"snippet::shapes::area".to_string()));
+    assert!(krate
+        .walk()
+        .any(|(path, item)| path == "snippet::shapes" && matches!(item, AnyItem::Module(_))));
+    assert!(paths.contains(&"snippet::glob::area".to_string()));
+
+    // rustdoc omits private imports, so glob imports are built by hand
+    let glob_import = |visibility: Visibility| {
+        let mut fixture = CrateFixture::new("fixture");
+        let private = fixture.module(fixture.root(), "private");
+        fixture.struct_(private, "Private", []);
+        let glob = fixture.module(fixture.root(), "glob");
+        let import = fixture.use_(glob, private);
+        let item = fixture.item_mut(import);
+        item.visibility = visibility;
+        let ItemEnum::Use(use_) = &mut item.inner else {
+            unreachable!()
+        };
+        use_.is_glob = true;
+        let krate = fixture.build();
+        let expanded = krate
+            .walk()
+            .any(|(path, _)| path == "fixture::glob::Private");
+        expanded
+    };
+    assert!(glob_import(Visibility::Public));
+    assert!(!glob_import(Visibility::Default));
+
+    // A private import re-exports nothing, even if its target is public, and neither does a glob import of its module
+    let import = |visibility: Visibility| {
+        let mut fixture = CrateFixture::new("fixture");
+        let private = fixture.module(fixture.root(), "private");
+        let secret = fixture.struct_(private, "Secret", []);
+        let inner = fixture.module(fixture.root(), "inner");
+        fixture.struct_(inner, "Shown", []);
+        let import = fixture.use_(inner, secret);
+        fixture.item_mut(import).visibility = visibility;
+        let glob = fixture.module(fixture.root(), "glob");
+        let import = fixture.use_(glob, inner);
+        let ItemEnum::Use(use_) = &mut fixture.item_mut(import).inner else {
+            unreachable!()
+        };
+        use_.is_glob = true;
+        let krate = fixture.build();
+        let paths = krate.walk().map(|(path, _)| path).collect::<Vec<_>>();
+        paths
+    };
+    let paths = import(Visibility::Public);
+    assert!(paths.contains(&"fixture::inner::Secret".to_string()));
+    assert!(paths.contains(&"fixture::glob::Secret".to_string()));
+    let paths = import(Visibility::Default);
+    assert!(paths.contains(&"fixture::glob::Shown".to_string()));
+    assert!(!paths.contains(&"fixture::inner::Secret".to_string()));
+    assert!(!paths.contains(&"fixture::glob::Secret".to_string()));
+}
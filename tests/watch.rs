@@ -0,0 +1,65 @@
+#![cfg(feature = "watch")]
+
+mod common;
+
+#[test]
+fn test_watch() {
+    use std::time::Duration;
+
+    use crate_inspector::watch::{CrateWatcher, WatchEvent};
+    use crate_inspector::CrateBuilder;
+
+    use common::{scratch_dir, TestBuilder};
+
+    let dir = scratch_dir(
+        "watch",
+        &[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"watched\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+            ),
+            ("src/lib.rs", "pub struct A;\n"),
+        ],
+    );
+
+    let (watch, events) = CrateWatcher::default()
+        .crate_builder(
+            CrateBuilder::default()
+                .for_tests()
+                .manifest_path(dir.join("Cargo.toml")),
+        )
+        .debounce(Duration::from_millis(100))
+        .diff(true)
+        .watch_channel()
+        .unwrap();
+    let timeout = Duration::from_secs(60);
+    let WatchEvent::Built { krate, diff } = events.recv_timeout(timeout).unwrap() else {
+        panic!("the initial build failed");
+    };
+    assert!(krate.get_struct("A").is_some());
+    assert!(diff.is_none());
+
+    std::fs::write(
+        dir.join("src").join("lib.rs"),
+        "pub struct A(pub u8);\n\npub struct B;\n",
+    )
+    .unwrap();
+    let WatchEvent::Built { krate, diff } = events.recv_timeout(timeout).unwrap() else {
+        panic!("the rebuild failed");
+    };
+    assert!(krate.get_struct("B").is_some());
+    let diff = diff.unwrap();
+    assert_eq!(
+        diff.added().map(|(path, _)| path).collect::<Vec<_>>(),
+        ["watched::A::0", "watched::B"]
+    );
+    assert_eq!(diff.removed().count(), 0);
+    // A unit struct became a tuple struct
+    assert_eq!(
+        diff.changed().map(|(path, _)| path).collect::<Vec<_>>(),
+        ["watched::A"]
+    );
+
+    watch.stop();
+    assert!(events.recv().is_err());
+}
@@ -0,0 +1,59 @@
+mod common;
+
+#[test]
+fn test_workspace() {
+    use crate_inspector::workspace::WorkspaceBuilder;
+    use crate_inspector::CrateBuilder;
+
+    use common::{scratch_dir, TestBuilder};
+
+    let dir = scratch_dir(
+        "workspace",
+        &[
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"a\", \"b\", \"app\", \"excluded\"]\nresolver = \"2\"\n",
+            ),
+            (
+                "a/Cargo.toml",
+                "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("a/src/lib.rs", "pub struct A;\n"),
+            (
+                "b/Cargo.toml",
+                "[package]\nname = \"b\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("b/src/lib.rs", "pub fn b() -> Missing {}\n"),
+            (
+                "app/Cargo.toml",
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("app/src/main.rs", "fn main() {}\n"),
+            (
+                "excluded/Cargo.toml",
+                "[package]\nname = \"excluded\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("excluded/src/lib.rs", "pub struct Excluded;\n"),
+        ],
+    );
+
+    let workspace = WorkspaceBuilder::default()
+        .manifest_path(dir.join("Cargo.toml"))
+        .crate_builder(CrateBuilder::default().for_tests())
+        .exclude(["excluded"])
+        .build()
+        .unwrap();
+    assert!(!workspace.is_ok());
+    // binary-only members are skipped
+    assert_eq!(
+        workspace.crates().map(|(name, _)| name).collect::<Vec<_>>(),
+        ["a"]
+    );
+    assert!(workspace
+        .get("a")
+        .is_some_and(|krate| krate.get_struct("A").is_some()));
+    assert_eq!(
+        workspace.errors().map(|(name, _)| name).collect::<Vec<_>>(),
+        ["b"]
+    );
+}